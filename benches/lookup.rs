@@ -0,0 +1,86 @@
+//! Benchmarks for looking up the term a date is in, with the built-in table and with a much
+//! bigger one.
+
+use chrono::{prelude::*, Duration};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use uoyweek::{builtin_terms, file, get_strict_term, get_term, midnight, zone::Tz, Term};
+
+/// Spreads `count` instants over the days from `first` to `last`, in a jumbled order.
+fn dates(first: NaiveDate, last: NaiveDate, count: i64) -> Vec<DateTime<Tz>> {
+    let days = (last - first).num_days();
+    (0..count)
+        // Multiplying by a prime mixes the days up without needing a random number generator.
+        .map(|i| first + Duration::days(i * 7919 % days))
+        .map(|date| midnight(date).expect("York has a midnight every day"))
+        .collect()
+}
+
+/// 100 years (300 terms) of made-up term dates, starting in 2000.
+fn big_table() -> Vec<Term> {
+    let mut toml = String::new();
+    for year in 2000..2100 {
+        for (name, start, end) in &[
+            ("Autumn", (year, 9, 25), (year, 12, 1)),
+            ("Spring", (year + 1, 1, 8), (year + 1, 3, 15)),
+            ("Summer", (year + 1, 4, 15), (year + 1, 6, 21)),
+        ] {
+            toml += &format!(
+                "[[term]]\nname = \"{}\"\nstart = \"{:04}-{:02}-{:02}\"\nend = \"{:04}-{:02}-{:02}\"\n",
+                name, start.0, start.1, start.2, end.0, end.1, end.2
+            );
+        }
+    }
+    file::from_toml(&toml).expect("made-up term dates are valid")
+}
+
+fn lookup(c: &mut Criterion) {
+    let builtin = builtin_terms();
+    let builtin_dates = dates(
+        builtin[0].start().date_naive(),
+        builtin[builtin.len() - 1].end().date_naive(),
+        1000,
+    );
+    c.bench_function("get_term (built-in)", |b| {
+        b.iter(|| {
+            builtin_dates
+                .iter()
+                .filter(|&&now| get_term(&builtin, now).is_some())
+                .count()
+        })
+    });
+    c.bench_function("get_strict_term (built-in)", |b| {
+        b.iter(|| {
+            builtin_dates
+                .iter()
+                .filter(|&&now| get_strict_term(&builtin, now).is_some())
+                .count()
+        })
+    });
+
+    let big = big_table();
+    let big_dates = dates(
+        NaiveDate::from_ymd_opt(2000, 9, 1).expect("date is valid"),
+        NaiveDate::from_ymd_opt(2101, 7, 1).expect("date is valid"),
+        1000,
+    );
+    c.bench_function("get_term (300 terms)", |b| {
+        b.iter(|| {
+            big_dates
+                .iter()
+                .filter(|&&now| get_term(&big, now).is_some())
+                .count()
+        })
+    });
+    c.bench_function("get_strict_term (300 terms)", |b| {
+        b.iter(|| {
+            big_dates
+                .iter()
+                .filter(|&&now| get_strict_term(&big, now).is_some())
+                .count()
+        })
+    });
+}
+
+criterion_group!(benches, lookup);
+criterion_main!(benches);
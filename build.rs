@@ -0,0 +1,86 @@
+//! Generates the built-in term dates from `terms.csv`, so that updating them doesn't mean
+//! touching any Rust.
+
+use std::{env, fmt::Write, fs, path::Path, process};
+
+/// A date in `terms.csv`, as `(year, month, day)`.
+type Date = (i32, u32, u32);
+
+fn main() {
+    println!("cargo:rerun-if-changed=terms.csv");
+    let csv = fs::read_to_string("terms.csv").unwrap_or_else(|e| fail(0, &e.to_string()));
+    let mut out = String::from("// Generated by build.rs from terms.csv.\n&[\n");
+    let mut rows = csv
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    match rows.next() {
+        Some((_, "name,start,end")) => {}
+        Some((line, _)) => fail(line, "expected a header row of `name,start,end`"),
+        None => fail(0, "no header row"),
+    }
+    for (line, row) in rows {
+        let (name, start, end) = match row.split(',').map(str::trim).collect::<Vec<_>>()[..] {
+            [name, start, end] => (name, start, end),
+            _ => fail(line, "expected three columns: name, start and end"),
+        };
+        if !["Autumn", "Spring", "Summer"].contains(&name) {
+            fail(
+                line,
+                &format!(
+                    "{:?} isn't a term (expected Autumn, Spring or Summer)",
+                    name
+                ),
+            );
+        }
+        let start = parse_date(start).unwrap_or_else(|| fail(line, &bad_date(start)));
+        let end = parse_date(end).unwrap_or_else(|| fail(line, &bad_date(end)));
+        if end < start {
+            fail(line, "term ends before it starts");
+        }
+        writeln!(out, "    (TermName::{}, {:?}, {:?}),", name, start, end)
+            .expect("writing to a String can't fail");
+    }
+    out.push_str("]\n");
+    let path = Path::new(&env::var("OUT_DIR").expect("cargo sets OUT_DIR")).join("terms.rs");
+    fs::write(path, out).unwrap_or_else(|e| fail(0, &e.to_string()));
+}
+
+/// Stops the build, pointing at `line` of `terms.csv` (unless it's 0).
+fn fail(line: usize, message: &str) -> ! {
+    if line == 0 {
+        eprintln!("error: terms.csv: {}", message);
+    } else {
+        eprintln!("error: terms.csv line {}: {}", line, message);
+    }
+    process::exit(1);
+}
+
+fn bad_date(s: &str) -> String {
+    format!("invalid date {:?} (expected YYYY-MM-DD)", s)
+}
+
+/// Parses a date like `2023-09-25`, checking that the day exists.
+fn parse_date(s: &str) -> Option<Date> {
+    let mut parts = s.split('-');
+    let (year, month, day) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(year), Some(month), Some(day), None) if year.len() == 4 => {
+            (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?)
+        }
+        _ => return None,
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    if (1..=days).contains(&day) {
+        Some((year, month, day))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,27 @@
+//! Prints the first day of each week of the 2023/24 academic year, and which term week it is.
+//!
+//! Run with `cargo run --example week_range`.
+
+use chrono::{Duration, NaiveDate};
+
+use uoyweek::{builtin_terms, format::Format, midnight, TermTable};
+
+fn main() {
+    let table = TermTable::new(builtin_terms()).expect("built-in term dates are valid");
+    let format: Format = "%t/%w".parse().expect("format is valid");
+    let mut date = NaiveDate::from_ymd_opt(2023, 8, 1).expect("date is valid");
+    let mut last = None;
+    while date < NaiveDate::from_ymd_opt(2024, 8, 1).expect("date is valid") {
+        let now = midnight(date).expect("York has a midnight every day in 2023/24");
+        let week = match table.week(now) {
+            Some(week) if week.strict => format.render(&week),
+            Some(week) => format!("({})", format.render(&week)),
+            None => "n/a".into(),
+        };
+        if last.as_ref() != Some(&week) {
+            println!("{}: {}", date, week);
+            last = Some(week);
+        }
+        date += Duration::days(1);
+    }
+}
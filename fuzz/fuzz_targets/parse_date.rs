@@ -0,0 +1,18 @@
+//! Checks that parsing a `--date` (or a line of `--stdin`) never panics, however strange the
+//! input, and nor does finding the start of the day it names.
+//!
+//! Run with `cargo +nightly fuzz run parse_date` from the root of the repository (after
+//! `cargo install cargo-fuzz`).
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use uoyweek::{midnight, when::When};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        if let Ok(When::Day(date)) = s.trim().parse::<When>() {
+            let _ = midnight(date);
+        }
+    }
+});
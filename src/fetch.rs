@@ -0,0 +1,253 @@
+//! Downloading term dates from the University's website.
+//!
+//! The term dates page lists each academic year as a table, with one row per term, e.g.
+//! "Autumn Term | Monday 25 September 2023 to Friday 1 December 2023". Rows that don't start
+//! with the name of a term are ignored.
+
+use std::{error::Error, fmt};
+
+use chrono::NaiveDate;
+use scraper::{Html, Selector};
+
+use crate::{Term, TermError, TermName};
+
+pub const TERM_DATES_URL: &str = "https://www.york.ac.uk/about/term-dates/";
+
+/// Term dates that couldn't be fetched.
+#[derive(Debug)]
+pub enum FetchError {
+    Http(reqwest::Error),
+    /// A row of the table named a term, but its dates didn't make sense.
+    BadRow(String),
+    /// The page didn't have any term dates on it.
+    NoTerms,
+    Term(TermError),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FetchError::Http(e) => write!(f, "{}", e),
+            FetchError::BadRow(row) => write!(f, "couldn't understand term dates {:?}", row),
+            FetchError::NoTerms => write!(f, "no term dates found"),
+            FetchError::Term(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for FetchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FetchError::Http(e) => Some(e),
+            FetchError::Term(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(e: reqwest::Error) -> FetchError {
+        FetchError::Http(e)
+    }
+}
+
+impl From<TermError> for FetchError {
+    fn from(e: TermError) -> FetchError {
+        FetchError::Term(e)
+    }
+}
+
+/// Downloads the term dates page and parses it, returning the terms sorted by start date.
+pub fn fetch() -> Result<Vec<Term>, FetchError> {
+    let html = reqwest::blocking::get(TERM_DATES_URL)?
+        .error_for_status()?
+        .text()?;
+    parse(&html)
+}
+
+/// Parses the term dates page, returning the terms sorted by start date.
+pub fn parse(html: &str) -> Result<Vec<Term>, FetchError> {
+    let document = Html::parse_document(html);
+    let rows = Selector::parse("tr").expect("selector is valid");
+    let cells = Selector::parse("th, td").expect("selector is valid");
+    let mut terms = vec![];
+    for row in document.select(&rows) {
+        let mut texts = row
+            .select(&cells)
+            .map(|cell| cell.text().collect::<Vec<_>>().join(" "));
+        let name = match texts.next().and_then(|label| parse_label(&label)) {
+            Some(name) => name,
+            None => continue,
+        };
+        let dates = texts.collect::<Vec<_>>().join(" ");
+        match parse_dates(&dates)[..] {
+            [start, end] if start <= end => terms.push(Term::new(name, start, end)?),
+            _ => return Err(FetchError::BadRow(dates)),
+        }
+    }
+    if terms.is_empty() {
+        return Err(FetchError::NoTerms);
+    }
+    terms.sort_unstable_by_key(|term| term.start());
+    Ok(terms)
+}
+
+/// Maps a row label like "Autumn Term" to the term it names.
+fn parse_label(label: &str) -> Option<TermName> {
+    let label = label.trim().to_lowercase();
+    if label.starts_with("autumn") {
+        Some(TermName::Autumn)
+    } else if label.starts_with("spring") {
+        Some(TermName::Spring)
+    } else if label.starts_with("summer") {
+        Some(TermName::Summer)
+    } else {
+        None
+    }
+}
+
+/// Finds all the dates written like "Monday 25 September 2023" in `text`.
+///
+/// The weekday is optional, and so is the year, if a later date has one (as in "25 September to
+/// 1 December 2023").
+fn parse_dates(text: &str) -> Vec<NaiveDate> {
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    // (day, month, year if given)
+    let mut found: Vec<(u32, u32, Option<i32>)> = vec![];
+    let mut i = 0;
+    while i + 1 < words.len() {
+        let day = words[i]
+            .trim_end_matches(|c: char| c.is_alphabetic())
+            .parse::<u32>();
+        match (day, parse_month(words[i + 1])) {
+            (Ok(day), Some(month)) => {
+                let year = words
+                    .get(i + 2)
+                    .filter(|word| word.len() == 4)
+                    .and_then(|word| word.parse::<i32>().ok());
+                found.push((day, month, year));
+                i += if year.is_some() { 3 } else { 2 };
+            }
+            _ => i += 1,
+        }
+    }
+    // Fill in missing years from the following date, going back a year if necessary.
+    let mut dates = vec![];
+    let mut next: Option<(u32, u32, i32)> = None;
+    for &(day, month, year) in found.iter().rev() {
+        let year = match (year, next) {
+            (Some(year), _) => year,
+            (None, Some((next_day, next_month, next_year))) => {
+                if (month, day) <= (next_month, next_day) {
+                    next_year
+                } else {
+                    next_year - 1
+                }
+            }
+            (None, None) => return vec![],
+        };
+        match NaiveDate::from_ymd_opt(year, month, day) {
+            Some(date) => dates.push(date),
+            None => return vec![],
+        }
+        next = Some((day, month, year));
+    }
+    dates.reverse();
+    dates
+}
+
+fn parse_month(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    let word = word.to_lowercase();
+    if word.len() < 3 {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .position(|month| month.starts_with(&word))
+        .map(|i| i as u32 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_terms;
+
+    /// A copy of the term dates page, cut down to two years.
+    const PAGE: &str = include_str!("../tests/data/term-dates.html");
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).expect("test dates are valid")
+    }
+
+    #[test]
+    fn page_matches_builtin_terms() {
+        let terms = parse(PAGE).unwrap();
+        let builtin: Vec<_> = builtin_terms()
+            .into_iter()
+            .filter(|term| (2023..=2024).contains(&term.academic_year_start()))
+            .collect();
+        assert_eq!(terms, builtin);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn page_saves_as_a_terms_file() {
+        let terms = parse(PAGE).unwrap();
+        let toml = crate::file::to_toml(&terms);
+        assert_eq!(toml.matches("[[term]]").count(), 6);
+        assert!(
+            toml.contains("name = \"Autumn\"\nstart = \"2023-09-25\"\nend = \"2023-12-01\"\n"),
+            "{}",
+            toml
+        );
+        assert_eq!(crate::file::from_toml(&toml).unwrap(), terms);
+    }
+
+    #[test]
+    fn missing_years_come_from_later_dates() {
+        assert_eq!(
+            parse_dates("Monday 23 September to Friday 29 November 2024"),
+            [date(2024, 9, 23), date(2024, 11, 29)]
+        );
+        // Across the new year, the first date is in the year before.
+        assert_eq!(
+            parse_dates("Mon 30 Dec to Fri 7 Mar 2025"),
+            [date(2024, 12, 30), date(2025, 3, 7)]
+        );
+        assert_eq!(parse_dates("23rd September to 29th November"), []);
+    }
+
+    #[test]
+    fn bad_rows_are_errors() {
+        let html = "<table><tr><td>Autumn Term</td><td>To be confirmed</td></tr></table>";
+        assert!(matches!(parse(html), Err(FetchError::BadRow(_))));
+        let html = "<table><tr><td>Autumn Term</td>\
+                    <td>Friday 1 December 2023 to Monday 25 September 2023</td></tr></table>";
+        assert!(matches!(parse(html), Err(FetchError::BadRow(_))));
+    }
+
+    #[test]
+    fn page_without_terms_is_an_error() {
+        assert!(matches!(
+            parse("<p>Term dates are moving.</p>"),
+            Err(FetchError::NoTerms)
+        ));
+    }
+}
@@ -0,0 +1,218 @@
+//! Reading and writing term dates as TOML, so that they can be updated without a rebuild.
+//!
+//! A terms file is a list of `[[term]]` tables, in any order:
+//!
+//! ```toml
+//! [[term]]
+//! name = "Autumn"      # "Autumn", "Spring" or "Summer"
+//! start = "2023-09-25" # the first day of term
+//! end = "2023-12-01"   # the last day of term (usually a Friday)
+//! reading_weeks = [6]  # optional: weeks when teaching pauses
+//! ```
+//!
+//! Dates must be quoted strings, not TOML's native date type.
+
+use std::{error::Error, fmt, fs, io, path::Path};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{Term, TermError, TermName};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TermsFile {
+    #[serde(rename = "term", default)]
+    terms: Vec<TermEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TermEntry {
+    name: TermName,
+    start: NaiveDate,
+    /// The last day of term, unlike `Term::end`.
+    end: NaiveDate,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    reading_weeks: Vec<i64>,
+}
+
+/// A terms file that couldn't be loaded.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Term(TermError),
+    /// A reading week that isn't one of the weeks of the term starting on `start`.
+    ReadingWeek {
+        term: TermName,
+        start: NaiveDate,
+        week: i64,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::Toml(e) => write!(f, "{}", e),
+            LoadError::Term(e) => write!(f, "{}", e),
+            LoadError::ReadingWeek { term, start, week } => write!(
+                f,
+                "reading week {} isn't in {} term starting {}",
+                week,
+                term.longname(),
+                start.format("%Y-%m-%d")
+            ),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(e) => Some(e),
+            LoadError::Toml(e) => Some(e),
+            LoadError::Term(e) => Some(e),
+            LoadError::ReadingWeek { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> LoadError {
+        LoadError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for LoadError {
+    fn from(e: toml::de::Error) -> LoadError {
+        LoadError::Toml(e)
+    }
+}
+
+impl From<TermError> for LoadError {
+    fn from(e: TermError) -> LoadError {
+        LoadError::Term(e)
+    }
+}
+
+/// Parses a terms file, returning the terms sorted by start date.
+pub fn from_toml(s: &str) -> Result<Vec<Term>, LoadError> {
+    let file: TermsFile = toml::from_str(s)?;
+    let mut terms = file
+        .terms
+        .into_iter()
+        .map(|entry| {
+            let term = Term::new(entry.name, entry.start, entry.end)?;
+            let term = term.with_reading_weeks(&entry.reading_weeks);
+            match entry
+                .reading_weeks
+                .iter()
+                .find(|&&week| !term.is_reading_week(week))
+            {
+                Some(&week) => Err(LoadError::ReadingWeek {
+                    term: entry.name,
+                    start: entry.start,
+                    week,
+                }),
+                None => Ok(term),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    terms.sort_unstable_by_key(|term| term.start());
+    Ok(terms)
+}
+
+/// Writes `terms` in the terms file format.
+pub fn to_toml(terms: &[Term]) -> String {
+    let file = TermsFile {
+        terms: terms
+            .iter()
+            .map(|term| TermEntry {
+                name: term.name(),
+                start: term.start().date_naive(),
+                end: term.last_day(),
+                reading_weeks: term.reading_weeks().collect(),
+            })
+            .collect(),
+    };
+    toml::to_string(&file).expect("terms are always serializable")
+}
+
+/// Reads and parses the terms file at `path`.
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Term>, LoadError> {
+    from_toml(&fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_terms;
+
+    #[test]
+    fn builtin_terms_round_trip() {
+        let terms = builtin_terms();
+        assert_eq!(from_toml(&to_toml(&terms)).unwrap(), terms);
+    }
+
+    #[test]
+    fn reading_weeks_round_trip() {
+        let toml = "[[term]]\nname = \"Autumn\"\nstart = \"2023-09-25\"\nend = \"2023-12-01\"\nreading_weeks = [6]\n";
+        let terms = from_toml(toml).unwrap();
+        assert!(terms[0].is_reading_week(6));
+        assert_eq!(to_toml(&terms), toml);
+    }
+
+    #[test]
+    fn backwards_term_is_rejected() {
+        let toml = "[[term]]\nname = \"Spring\"\nstart = \"2025-01-06\"\nend = \"2024-03-14\"\n";
+        assert!(matches!(
+            from_toml(toml),
+            Err(LoadError::Term(TermError::EndsBeforeStart((
+                TermName::Spring,
+                _
+            ))))
+        ));
+    }
+
+    #[test]
+    fn malformed_file_is_an_error() {
+        assert!(matches!(
+            from_toml("[[term]]\nname = \"Winter\"\n"),
+            Err(LoadError::Toml(_))
+        ));
+    }
+
+    #[test]
+    fn terms_are_sorted() {
+        let toml = "[[term]]\nname = \"Spring\"\nstart = \"2024-01-08\"\nend = \"2024-03-15\"\n\n\
+                    [[term]]\nname = \"Autumn\"\nstart = \"2023-09-25\"\nend = \"2023-12-01\"\n";
+        let names: Vec<_> = from_toml(toml).unwrap().iter().map(Term::name).collect();
+        assert_eq!(names, [TermName::Autumn, TermName::Spring]);
+    }
+
+    #[test]
+    fn reading_week_must_be_in_term() {
+        let toml = "[[term]]\nname = \"Autumn\"\nstart = \"2023-09-25\"\nend = \"2023-12-01\"\nreading_weeks = [12]\n";
+        let error = from_toml(toml).unwrap_err();
+        assert!(matches!(
+            error,
+            LoadError::ReadingWeek {
+                term: TermName::Autumn,
+                week: 12,
+                ..
+            }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "reading week 12 isn't in Autumn term starting 2023-09-25"
+        );
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        assert!(matches!(
+            load("/nonexistent/terms.toml"),
+            Err(LoadError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,558 @@
+//! Custom layouts for week information, like `strftime` but for term weeks.
+//!
+//! | Token | Meaning              | Example  |
+//! |-------|----------------------|----------|
+//! | `%t`  | Short term name      | `Aut`    |
+//! | `%L`  | Long term name       | `Autumn` |
+//! | `%w`  | Week number          | `5`      |
+//! | `%a`  | Short weekday name   | `Mon`    |
+//! | `%A`  | Long weekday name    | `Monday` |
+//! | `%%`  | A literal `%`        | `%`      |
+//!
+//! Anything else is copied as-is.
+//!
+//! With the `locale` feature, weekdays can be named in other languages (see
+//! `Format::with_locale`), but terms are always named in English.
+//!
+//! There are also the fixed descriptions that `uoyweek` prints, like `describe_progress` and
+//! `calendar`.
+
+use std::{error::Error, fmt, str::FromStr};
+
+#[cfg(feature = "locale")]
+use chrono::{format::StrftimeItems, Locale};
+use chrono::{prelude::*, Duration};
+
+use crate::{start_of_day, zone::Tz, Boundary, Term, TermTable, WeekInfo};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Item {
+    Literal(String),
+    ShortTerm,
+    LongTerm,
+    Week,
+    ShortWeekday,
+    LongWeekday,
+}
+
+/// A parsed format string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Format {
+    items: Vec<Item>,
+    /// How many digits to zero-pad week numbers to.
+    width: usize,
+    /// The language to name weekdays in, if not English.
+    #[cfg(feature = "locale")]
+    locale: Option<Locale>,
+}
+
+// `Locale` is a plain enum, but it's only `PartialEq`.
+impl Eq for Format {}
+
+/// A format string that couldn't be parsed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FormatError {
+    /// `%` followed by something other than a known token.
+    UnknownToken(char),
+    /// `%` at the end of the string.
+    Trailing,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::UnknownToken(c) => write!(f, "unknown format token %{}", c),
+            FormatError::Trailing => write!(f, "format string ends with a lone %"),
+        }
+    }
+}
+
+impl Error for FormatError {}
+
+impl FromStr for Format {
+    type Err = FormatError;
+
+    fn from_str(s: &str) -> Result<Format, FormatError> {
+        let mut items = vec![];
+        let mut literal = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                literal.push(c);
+                continue;
+            }
+            let item = match chars.next().ok_or(FormatError::Trailing)? {
+                '%' => {
+                    literal.push('%');
+                    continue;
+                }
+                't' => Item::ShortTerm,
+                'L' => Item::LongTerm,
+                'w' => Item::Week,
+                'a' => Item::ShortWeekday,
+                'A' => Item::LongWeekday,
+                c => return Err(FormatError::UnknownToken(c)),
+            };
+            if !literal.is_empty() {
+                items.push(Item::Literal(literal.split_off(0)));
+            }
+            items.push(item);
+        }
+        if !literal.is_empty() {
+            items.push(Item::Literal(literal));
+        }
+        Ok(Format {
+            items,
+            width: 0,
+            #[cfg(feature = "locale")]
+            locale: None,
+        })
+    }
+}
+
+impl Default for Format {
+    /// The usual `Aut/5/Mon` format.
+    fn default() -> Format {
+        "%t/%w/%a".parse().expect("default format is valid")
+    }
+}
+
+impl Format {
+    /// Like the default format, but with full names: `Autumn/5/Monday`.
+    pub fn long() -> Format {
+        "%L/%w/%A".parse().expect("long format is valid")
+    }
+    /// Returns the same format, but with week numbers zero-padded to `width` digits, like
+    /// `Aut/05/Mon`.
+    pub fn with_width(self, width: usize) -> Format {
+        Format { width, ..self }
+    }
+    /// Returns the same format, but with weekdays named in the language of `locale`, like
+    /// `Aut/5/lun.` for `fr_FR`.
+    #[cfg(feature = "locale")]
+    pub fn with_locale(self, locale: Locale) -> Format {
+        Format {
+            locale: Some(locale),
+            ..self
+        }
+    }
+    /// Formats the weekday of `date` with `strftime`-style `format`, in the language of the
+    /// locale (if any).
+    fn weekday(&self, date: NaiveDate, format: &str) -> String {
+        #[cfg(feature = "locale")]
+        if let Some(locale) = self.locale {
+            return chrono::format::DelayedFormat::new_with_locale(
+                Some(date),
+                None,
+                StrftimeItems::new_with_locale(format, locale),
+                locale,
+            )
+            .to_string();
+        }
+        date.format(format).to_string()
+    }
+    pub fn render(&self, week: &WeekInfo) -> String {
+        let mut out = String::new();
+        for item in &self.items {
+            match item {
+                Item::Literal(s) => out.push_str(s),
+                Item::ShortTerm => out.push_str(week.term.shortname()),
+                Item::LongTerm => out.push_str(week.term.longname()),
+                Item::Week => out.push_str(&format!("{:01$}", week.week, self.width)),
+                Item::ShortWeekday => out.push_str(&self.weekday(week.date, "%a")),
+                Item::LongWeekday => out.push_str(&self.weekday(week.date, "%A")),
+            }
+        }
+        out
+    }
+}
+
+/// Describes how far through term `now` is, like `(week 5 of 10, 50% through term)`.
+pub fn describe_progress(table: &TermTable, now: DateTime<Tz>) -> String {
+    let term = match table.current(now) {
+        Some(term) => term,
+        None => return "(not in term)".into(),
+    };
+    let total = term.total_weeks();
+    let week = term.week_number(now);
+    let position = term
+        .weeks()
+        .position(|(number, _, _)| number == week)
+        .expect("the current week is in term") as i64
+        + 1;
+    // Go by week numbers, so that with an arrivals week, Autumn still has weeks 1 to 10.
+    format!(
+        "(week {} of {}, {}% through term)",
+        week,
+        week + total - position,
+        (100 * position + total / 2) / total
+    )
+}
+
+/// Describes how long is left of the term `now` is in, like `2 weeks 3 days left in Autumn
+/// term`, or how long it is until the next one.
+pub fn describe_remaining(table: &TermTable, now: DateTime<Tz>) -> Option<String> {
+    let (term, boundary) = table.next_boundary(now)?;
+    Some(match boundary {
+        Boundary::Start => format!(
+            "{} until {} term",
+            weeks_and_days((term.start().date_naive() - now.date_naive()).num_days()),
+            term.name().longname()
+        ),
+        // Today counts as a day left.
+        Boundary::End => format!(
+            "{} left in {} term",
+            weeks_and_days((term.end().date_naive() - now.date_naive()).num_days()),
+            term.name().longname()
+        ),
+    })
+}
+
+/// Describes the vacation `now` is in, like `Christmas vacation week 3, 2 weeks until Spring`.
+pub fn describe_vacation(table: &TermTable, now: DateTime<Tz>) -> Option<String> {
+    let vacation = table.vacation(now)?;
+    let term = table.next(now)?;
+    let days = (term.loose_start().date_naive() - now.date_naive()).num_days();
+    Some(format!(
+        "{} vacation week {}, {} until {}",
+        vacation.name().longname(),
+        vacation.week_number(now),
+        weeks_and_days(days),
+        term.name().longname()
+    ))
+}
+
+/// Says if a term starts on the day of `now`, like `Autumn term starts today`, or if one ended the
+/// day before.
+pub fn describe_boundary(table: &TermTable, now: DateTime<Tz>) -> Option<String> {
+    let (term, boundary) = table.boundary_on(now.date_naive())?;
+    Some(match boundary {
+        Boundary::Start => format!("{} term starts today", term.name().longname()),
+        Boundary::End => format!("{} term ended yesterday", term.name().longname()),
+    })
+}
+
+/// Says when the next term starts or ends, like `Spring term starts in 3 days (2024-01-08)`.
+pub fn describe_next_event(table: &TermTable, now: DateTime<Tz>) -> Option<String> {
+    let (term, boundary) = table.next_boundary(now)?;
+    let (verb, date) = match boundary {
+        Boundary::Start => ("starts", term.start().date_naive()),
+        Boundary::End => ("ends", term.last_day()),
+    };
+    let countdown = match (date - now.date_naive()).num_days() {
+        0 => "today".into(),
+        days => format!("in {}", plural(days, "day")),
+    };
+    Some(format!(
+        "{} term {} {} ({})",
+        term.name().longname(),
+        verb,
+        countdown,
+        date.format("%Y-%m-%d")
+    ))
+}
+
+/// Describes how far apart `from` and `to` are in term weeks, like `12 teaching weeks apart,
+/// crossing Spring and Summer terms`.
+pub fn diff(table: &TermTable, from: DateTime<Tz>, to: DateTime<Tz>) -> String {
+    let weeks = table.teaching_weeks_between(from, to);
+    let names: Vec<_> = table
+        .terms_between(from, to)
+        .into_iter()
+        .map(|term| term.name().longname())
+        .collect();
+    let terms = match &names[..] {
+        [] => "not in term".into(),
+        [name] => format!("in {} term", name),
+        [names @ .., last] => format!("crossing {} and {} terms", names.join(", "), last),
+    };
+    format!("{} apart, {}", plural(weeks, "teaching week"), terms)
+}
+
+/// Lays out the weeks of `term` like a calendar, one row per week, labelled with the week number
+/// and with any days out of term in parentheses.
+pub fn calendar(term: &Term) -> String {
+    let mut weeks = term.weeks().peekable();
+    let first_day = match weeks.peek() {
+        Some(&(_, start, _)) => start.date_naive(),
+        None => return String::new(),
+    };
+    let mut out = format!(
+        "{} term {}\nWeek",
+        term.name().longname(),
+        term.academic_year()
+    );
+    for i in 0..7 {
+        out += &(first_day + Duration::days(i)).format("  %a").to_string();
+    }
+    out.push('\n');
+    for (row, (number, start, _)) in weeks.enumerate() {
+        let mut line = format!("{:>4}", number);
+        let mut months = Vec::new();
+        for i in 0..7 {
+            // Step over dates rather than 24 hours at a time, which the clock changes throw off.
+            let day = start.date_naive() + Duration::days(i);
+            let text = day.format("%-d").to_string();
+            if term.range().contains(start_of_day(day)) {
+                line += &format!(" {:>3} ", text);
+            } else {
+                line += &format!(" {:>4}", format!("({})", text));
+            }
+            // Say which month it is at the start of each month (and of the term).
+            if day.day() == 1 || (row, i) == (0, 0) {
+                months.push(day.format("%B %Y").to_string());
+            }
+        }
+        if !months.is_empty() {
+            line += &format!("  {}", months.join(" / "));
+        }
+        out += line.trim_end();
+        out.push('\n');
+    }
+    out
+}
+
+/// Describes `now` as Prometheus gauges. The week number is left out of term, and the days until
+/// the next term after the last known one.
+pub fn metrics(table: &TermTable, now: DateTime<Tz>) -> String {
+    let mut out = String::from(
+        "# HELP uoyweek_in_term Whether it's in term, or (if not strict) in a week of term.\n\
+         # TYPE uoyweek_in_term gauge\n",
+    );
+    out += &format!(
+        "uoyweek_in_term{{strict=\"true\"}} {}\n",
+        table.is_term_time(now) as u8
+    );
+    out += &format!(
+        "uoyweek_in_term{{strict=\"false\"}} {}\n",
+        table.is_teaching_week(now) as u8
+    );
+    if let Some(week) = table.week(now) {
+        out += "# HELP uoyweek_week_number The week of term.\n\
+                # TYPE uoyweek_week_number gauge\n";
+        out += &format!("uoyweek_week_number {}\n", week.week);
+    }
+    if let Some(term) = table.next(now) {
+        out += "# HELP uoyweek_days_until_next_term Days until the next term starts.\n\
+                # TYPE uoyweek_days_until_next_term gauge\n";
+        out += &format!(
+            "uoyweek_days_until_next_term {}\n",
+            (term.start().date_naive() - now.date_naive()).num_days()
+        );
+    }
+    out
+}
+
+/// Describes a number of days like `2 weeks 3 days`.
+fn weeks_and_days(days: i64) -> String {
+    match (days / 7, days % 7) {
+        (weeks, 0) => plural(weeks, "week"),
+        (0, days) => plural(days, "day"),
+        (weeks, days) => format!("{} {}", plural(weeks, "week"), plural(days, "day")),
+    }
+}
+
+/// Counts `n` of `unit`, like `1 week` or `3 weeks`.
+pub fn plural(n: i64, unit: &str) -> String {
+    if n == 1 {
+        format!("{} {}", n, unit)
+    } else {
+        format!("{} {}s", n, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builtin_terms, midnight};
+
+    fn table() -> TermTable {
+        TermTable::new(builtin_terms()).unwrap()
+    }
+
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Tz> {
+        let date = NaiveDate::from_ymd_opt(year, month, day).expect("test dates are valid");
+        midnight(date).unwrap() + Duration::hours(12)
+    }
+
+    #[test]
+    fn progress_through_term() {
+        assert_eq!(
+            describe_progress(&table(), at(2023, 10, 23)),
+            "(week 5 of 10, 50% through term)"
+        );
+        assert_eq!(
+            describe_progress(&table(), at(2023, 12, 25)),
+            "(not in term)"
+        );
+    }
+
+    #[test]
+    fn time_remaining() {
+        assert_eq!(
+            describe_remaining(&table(), at(2023, 11, 28)).as_deref(),
+            Some("4 days left in Autumn term")
+        );
+        assert_eq!(
+            describe_remaining(&table(), at(2023, 12, 25)).as_deref(),
+            Some("2 weeks until Spring term")
+        );
+    }
+
+    #[test]
+    fn vacations() {
+        assert_eq!(
+            describe_vacation(&table(), at(2023, 12, 25)).as_deref(),
+            Some("Christmas vacation week 4, 2 weeks until Spring")
+        );
+        assert_eq!(describe_vacation(&table(), at(2023, 10, 23)), None);
+    }
+
+    #[test]
+    fn next_event() {
+        assert_eq!(
+            describe_next_event(&table(), at(2024, 1, 5)).as_deref(),
+            Some("Spring term starts in 3 days (2024-01-08)")
+        );
+        assert_eq!(
+            describe_next_event(&table(), at(2023, 12, 1)).as_deref(),
+            Some("Autumn term ends today (2023-12-01)")
+        );
+    }
+
+    #[test]
+    fn boundaries() {
+        assert_eq!(
+            describe_boundary(&table(), at(2024, 1, 8)).as_deref(),
+            Some("Spring term starts today")
+        );
+        assert_eq!(
+            describe_boundary(&table(), at(2023, 12, 2)).as_deref(),
+            Some("Autumn term ended yesterday")
+        );
+        assert_eq!(describe_boundary(&table(), at(2023, 12, 3)), None);
+    }
+
+    #[test]
+    fn differences() {
+        assert_eq!(
+            diff(&table(), at(2023, 10, 2), at(2023, 10, 16)),
+            "2 teaching weeks apart, in Autumn term"
+        );
+        // The vacation is skipped, and the order doesn't matter.
+        let crossing = "12 teaching weeks apart, crossing Spring and Summer terms";
+        assert_eq!(diff(&table(), at(2024, 1, 15), at(2024, 5, 6)), crossing);
+        assert_eq!(diff(&table(), at(2024, 5, 6), at(2024, 1, 15)), crossing);
+    }
+
+    #[test]
+    fn metrics_out_of_term() {
+        let out = metrics(&table(), at(2023, 12, 25));
+        assert!(out.contains("uoyweek_in_term{strict=\"true\"} 0\n"));
+        assert!(!out.contains("uoyweek_week_number"));
+        assert!(out.contains("uoyweek_days_until_next_term 14\n"));
+    }
+
+    #[test]
+    fn calendar_has_a_row_per_week() {
+        for term in builtin_terms() {
+            let rows = calendar(&term).lines().count() - 2;
+            assert_eq!(rows as i64, term.total_weeks(), "{}", term);
+        }
+    }
+
+    #[test]
+    fn calendar_names_both_months_in_the_first_row() {
+        let terms = builtin_terms();
+        let autumn = terms
+            .iter()
+            .find(|term| term.start().date_naive() == NaiveDate::from_ymd_opt(2023, 9, 25).unwrap())
+            .unwrap();
+        let out = calendar(autumn);
+        let rows: Vec<_> = out.lines().collect();
+        assert_eq!(rows[0], "Autumn term 2023/24");
+        assert_eq!(rows[1], "Week  Mon  Tue  Wed  Thu  Fri  Sat  Sun");
+        assert!(
+            rows[2].ends_with("  September 2023 / October 2023"),
+            "{}",
+            rows[2]
+        );
+        assert_eq!(rows[3], "   2   2    3    4    5    6    7    8");
+    }
+
+    #[test]
+    fn calendar_shows_each_day_once_over_a_clock_change() {
+        // Like `uoyweek --weekend-next calendar --term aut --year 2023`: the clocks go back on
+        // Sunday 29 October, in the middle of week 6.
+        let autumn = Term::new(
+            crate::TermName::Autumn,
+            NaiveDate::from_ymd_opt(2023, 9, 25).unwrap(),
+            NaiveDate::from_ymd_opt(2023, 12, 1).unwrap(),
+        )
+        .unwrap()
+        .with_week_start(Weekday::Sat)
+        .unwrap();
+        let out = calendar(&autumn);
+        let rows: Vec<_> = out.lines().collect();
+        assert_eq!(rows[1], "Week  Sat  Sun  Mon  Tue  Wed  Thu  Fri");
+        assert_eq!(
+            rows[7],
+            "   6  28   29   30   31    1    2    3   November 2023"
+        );
+        assert_eq!(
+            rows[11],
+            "  10  25   26   27   28   29   30    1   December 2023"
+        );
+    }
+
+    #[test]
+    fn plurals() {
+        assert_eq!(plural(1, "week"), "1 week");
+        assert_eq!(plural(0, "week"), "0 weeks");
+        assert_eq!(weeks_and_days(9), "1 week 2 days");
+        assert_eq!(weeks_and_days(14), "2 weeks");
+    }
+
+    #[test]
+    fn week_numbers_are_padded_to_width() {
+        let week = table().week(at(2023, 10, 9)).unwrap();
+        assert_eq!(Format::default().with_width(2).render(&week), "Aut/03/Mon");
+        assert_eq!(
+            Format::long().with_width(3).render(&week),
+            "Autumn/003/Monday"
+        );
+        // Longer numbers aren't cut short.
+        let week = table().week(at(2023, 12, 1)).unwrap();
+        assert_eq!(Format::default().with_width(1).render(&week), "Aut/10/Fri");
+    }
+
+    #[test]
+    fn format_strings_are_parsed() {
+        let week = table().week(at(2023, 10, 9)).unwrap();
+        let format: Format = "%L week %w (%A)".parse().unwrap();
+        assert_eq!(format.render(&week), "Autumn week 3 (Monday)");
+        let format: Format = "%t: 100%%".parse().unwrap();
+        assert_eq!(format.render(&week), "Aut: 100%");
+        assert_eq!(
+            "%t/%q".parse::<Format>(),
+            Err(FormatError::UnknownToken('q'))
+        );
+        assert_eq!("week %w%".parse::<Format>(), Err(FormatError::Trailing));
+    }
+
+    #[cfg(feature = "locale")]
+    #[test]
+    fn weekdays_in_french() {
+        let week = table().week(at(2023, 10, 9)).unwrap();
+        let french = Locale::fr_FR;
+        assert_eq!(
+            Format::default().with_locale(french).render(&week),
+            "Aut/3/lun."
+        );
+        // Term names stay in English.
+        assert_eq!(
+            Format::long().with_locale(french).render(&week),
+            "Autumn/3/lundi"
+        );
+    }
+}
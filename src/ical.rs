@@ -0,0 +1,90 @@
+//! Exporting term weeks as an iCalendar (RFC 5545) feed.
+
+use std::fmt::Write;
+
+use chrono::prelude::*;
+
+use crate::Term;
+
+/// Makes a calendar with an all-day event for each week of each term, e.g. "Aut/3".
+///
+/// `stamp` is used as the `DTSTAMP` of every event, and should usually be the current time.
+pub fn calendar(terms: &[Term], stamp: DateTime<Utc>) -> String {
+    // RFC 5545 requires CRLF line endings.
+    let mut out = String::from(
+        "BEGIN:VCALENDAR\r\n\
+         VERSION:2.0\r\n\
+         PRODID:-//uoyweek//uoyweek//EN\r\n\
+         CALSCALE:GREGORIAN\r\n\
+         X-WR-CALNAME:University of York term weeks\r\n",
+    );
+    for term in terms {
+        for (week, start, end) in term.weeks() {
+            // The UID only has to be unique, but this way it's also stable across exports.
+            write!(
+                out,
+                "BEGIN:VEVENT\r\n\
+                 UID:{term_start}-{term}-{week}@uoyweek\r\n\
+                 DTSTAMP:{stamp}\r\n\
+                 DTSTART;VALUE=DATE:{start}\r\n\
+                 DTEND;VALUE=DATE:{end}\r\n\
+                 SUMMARY:{short}/{week}\r\n\
+                 TRANSP:TRANSPARENT\r\n\
+                 END:VEVENT\r\n",
+                term_start = term.start().format("%Y%m%d"),
+                term = term.name().shortname().to_lowercase(),
+                week = week,
+                stamp = stamp.format("%Y%m%dT%H%M%SZ"),
+                start = start.format("%Y%m%d"),
+                end = end.format("%Y%m%d"),
+                short = term.name().shortname(),
+            )
+            .expect("writing to a String can't fail");
+        }
+    }
+    out.push_str("END:VCALENDAR\r\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_terms;
+
+    fn stamp() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 10, 9, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn an_event_per_week() {
+        let terms = builtin_terms();
+        let out = calendar(&terms, stamp());
+        let weeks: i64 = terms.iter().map(Term::total_weeks).sum();
+        assert_eq!(out.matches("BEGIN:VEVENT\r\n").count() as i64, weeks);
+        assert_eq!(out.matches("END:VEVENT\r\n").count() as i64, weeks);
+        assert!(out.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(out.ends_with("END:VCALENDAR\r\n"));
+        // Every line ends with CRLF.
+        assert!(!out.replace("\r\n", "").contains('\n'));
+    }
+
+    #[test]
+    fn weeks_are_all_day_events() {
+        let terms: Vec<_> = builtin_terms()
+            .into_iter()
+            .filter(|term| term.academic_year() == "2023/24")
+            .take(1)
+            .collect();
+        let out = calendar(&terms, stamp());
+        assert!(out.contains(
+            "BEGIN:VEVENT\r\n\
+             UID:20230925-aut-3@uoyweek\r\n\
+             DTSTAMP:20231009T120000Z\r\n\
+             DTSTART;VALUE=DATE:20231009\r\n\
+             DTEND;VALUE=DATE:20231016\r\n\
+             SUMMARY:Aut/3\r\n\
+             TRANSP:TRANSPARENT\r\n\
+             END:VEVENT\r\n"
+        ));
+    }
+}
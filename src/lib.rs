@@ -0,0 +1,1632 @@
+use std::{error::Error, fmt, str::FromStr, sync::OnceLock};
+
+use chrono::{prelude::*, Duration};
+#[cfg(feature = "serde")]
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+
+use crate::zone::{London, Tz};
+
+#[cfg(feature = "fetch")]
+pub mod fetch;
+#[cfg(feature = "serde")]
+pub mod file;
+pub mod format;
+pub mod ical;
+mod range;
+mod table;
+pub mod when;
+pub mod zone;
+
+pub use range::DateRange;
+pub use table::TermTable;
+
+/// The terms of an academic year, which sort in the order they come in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TermName {
+    Autumn,
+    Spring,
+    Summer,
+}
+use TermName::*;
+
+impl TermName {
+    pub fn shortname(&self) -> &'static str {
+        match self {
+            Autumn => "Aut",
+            Spring => "Spr",
+            Summer => "Sum",
+        }
+    }
+    pub fn longname(&self) -> &'static str {
+        match self {
+            Autumn => "Autumn",
+            Spring => "Spring",
+            Summer => "Summer",
+        }
+    }
+    /// A picture of the season, like 🍂 for Autumn.
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Autumn => "🍂",
+            Spring => "🌱",
+            Summer => "☀️",
+        }
+    }
+}
+
+/// A string that isn't the name of a term.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTermNameError(String);
+
+impl fmt::Display for ParseTermNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?} isn't a term (expected Autumn, Spring, Summer, or Aut, Spr, Sum)",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseTermNameError {}
+
+impl FromStr for TermName {
+    type Err = ParseTermNameError;
+
+    /// Parses a short or long term name, ignoring case.
+    fn from_str(s: &str) -> Result<TermName, ParseTermNameError> {
+        [Autumn, Spring, Summer]
+            .iter()
+            .copied()
+            .find(|name| {
+                s.eq_ignore_ascii_case(name.shortname()) || s.eq_ignore_ascii_case(name.longname())
+            })
+            .ok_or_else(|| ParseTermNameError(s.to_string()))
+    }
+}
+
+/// The break after each term.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VacationName {
+    Christmas,
+    Easter,
+    Summer,
+}
+
+impl VacationName {
+    /// The vacation which follows `term`.
+    pub fn after(term: TermName) -> VacationName {
+        match term {
+            Autumn => VacationName::Christmas,
+            Spring => VacationName::Easter,
+            Summer => VacationName::Summer,
+        }
+    }
+    pub fn longname(&self) -> &'static str {
+        match self {
+            VacationName::Christmas => "Christmas",
+            VacationName::Easter => "Easter",
+            VacationName::Summer => "Summer",
+        }
+    }
+    /// A picture of the holiday, like 🎄 for Christmas.
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            VacationName::Christmas => "🎄",
+            VacationName::Easter => "🐣",
+            VacationName::Summer => "🏖️",
+        }
+    }
+}
+
+/// The time between two terms.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Vacation {
+    name: VacationName,
+    /// The instant the previous term ends.
+    start: DateTime<Tz>,
+    /// The instant the next term starts.
+    end: DateTime<Tz>,
+    /// The start of the first week that's entirely out of term.
+    first_week: DateTime<Tz>,
+}
+
+impl Vacation {
+    /// The vacation from the end of `before` to the start of `after`, which should be the next
+    /// term.
+    pub fn between(before: &Term, after: &Term) -> Vacation {
+        Vacation {
+            name: VacationName::after(before.name()),
+            start: before.end(),
+            end: after.start(),
+            first_week: before.loose_end(),
+        }
+    }
+    pub fn name(&self) -> VacationName {
+        self.name
+    }
+    /// Returns the first instant of the vacation, i.e. the instant the previous term ends.
+    pub fn start(&self) -> DateTime<Tz> {
+        self.start
+    }
+    /// Returns the instant after the vacation, i.e. the instant the next term starts.
+    pub fn end(&self) -> DateTime<Tz> {
+        self.end
+    }
+    /// Returns the week of the vacation that `dt` is in, where week 1 is the first week that's
+    /// entirely out of term (the rest of the week the previous term ends in is still that
+    /// term's last week).
+    pub fn week_number(&self, dt: DateTime<Tz>) -> i64 {
+        (dt.date_naive() - self.first_week.date_naive())
+            .num_days()
+            .div_euclid(7)
+            + 1
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Term {
+    name: TermName,
+    /// The first instant of the term.
+    start: DateTime<Tz>,
+    /// The instant after the term ends.
+    end: DateTime<Tz>,
+    loose_start: DateTime<Tz>,
+    loose_end: DateTime<Tz>,
+    week_start: Weekday,
+    /// Whether the week before term (when new students arrive) counts as a week of term.
+    arrivals_week: bool,
+    /// The number of the week starting at `loose_start`.
+    first_week: i64,
+    /// Bit `n` is set if week `n` is a reading week.
+    reading_weeks: u64,
+}
+
+impl Term {
+    /// Makes a term whose first day is `start` and whose last day is `end` (usually a Friday),
+    /// in York, just like the built-in ones.
+    ///
+    /// The term has to end on or after the day it starts, and start and end at midnight.
+    pub fn new(name: TermName, start: NaiveDate, end: NaiveDate) -> Result<Term, TermError> {
+        if end < start {
+            return Err(TermError::EndsBeforeStart((name, start)));
+        }
+        let start = midnight(start)?;
+        let end = midnight(end + Duration::days(1))?;
+        let term = Term {
+            name,
+            start,
+            end,
+            loose_start: start,
+            loose_end: end,
+            week_start: Weekday::Mon,
+            arrivals_week: false,
+            first_week: 1,
+            reading_weeks: 0,
+        };
+        Ok(term.realign()?)
+    }
+    /// Works out `loose_start` and `loose_end` again.
+    fn realign(mut self) -> Result<Term, NoMidnight> {
+        let mut loose_start = start_of_week(self.start.date_naive(), self.week_start);
+        if self.arrivals_week {
+            loose_start -= Duration::weeks(1);
+        }
+        let mut loose_end = self.end.date_naive();
+        while loose_end.weekday() != self.week_start {
+            loose_end += Duration::days(1);
+        }
+        self.loose_start = midnight(loose_start)?;
+        self.loose_end = midnight(loose_end)?;
+        Ok(self)
+    }
+    /// Returns the same term, but with weeks starting on `week_start` instead of Monday.
+    pub fn with_week_start(&self, week_start: Weekday) -> Result<Term, NoMidnight> {
+        Term {
+            week_start,
+            ..*self
+        }
+        .realign()
+    }
+    /// Returns the same term, but starting a week earlier with week 0, for new students'
+    /// arrival. Later weeks keep their numbers.
+    pub fn with_arrivals_week(&self) -> Result<Term, NoMidnight> {
+        Term {
+            arrivals_week: true,
+            first_week: if self.arrivals_week {
+                self.first_week
+            } else {
+                self.first_week - 1
+            },
+            ..*self
+        }
+        .realign()
+    }
+    /// Returns the same term, but with its first week (which is the arrivals week, with
+    /// `with_arrivals_week`) numbered `number`, and the rest following on from it.
+    pub fn with_first_week(&self, number: i64) -> Term {
+        // Keep the same weeks marked as reading weeks, under their new numbers.
+        let shift = number - self.first_week;
+        let reading_weeks = if shift >= 0 {
+            self.reading_weeks.checked_shl(shift as u32)
+        } else {
+            self.reading_weeks.checked_shr(-shift as u32)
+        };
+        Term {
+            first_week: number,
+            reading_weeks: reading_weeks.unwrap_or(0),
+            ..*self
+        }
+    }
+    /// Returns the same term, but with the given weeks marked as reading weeks, when teaching
+    /// pauses. Any weeks that aren't in the term are ignored.
+    pub fn with_reading_weeks(&self, weeks: &[i64]) -> Term {
+        let reading_weeks = self
+            .weeks()
+            .map(|(number, _, _)| number)
+            .filter(|number| weeks.contains(number) && (0..64).contains(number))
+            .fold(0, |bits, number| bits | 1 << number);
+        Term {
+            reading_weeks,
+            ..*self
+        }
+    }
+    /// Returns whether week `week` of this term is a reading week.
+    pub fn is_reading_week(&self, week: i64) -> bool {
+        (0..64).contains(&week) && self.reading_weeks & 1 << week != 0
+    }
+    /// Returns the numbers of this term's reading weeks, in order.
+    pub fn reading_weeks(&self) -> impl Iterator<Item = i64> + '_ {
+        (0..64).filter(move |&week| self.is_reading_week(week))
+    }
+    pub fn name(&self) -> TermName {
+        self.name
+    }
+    pub fn start(&self) -> DateTime<Tz> {
+        self.start
+    }
+    pub fn end(&self) -> DateTime<Tz> {
+        self.end
+    }
+    /// Returns the last day of term (usually a Friday), i.e. the day before `end`.
+    pub fn last_day(&self) -> NaiveDate {
+        self.end.date_naive() - Duration::days(1)
+    }
+    /// Returns the calendar year in which this term's academic year starts (e.g. 2023 for any
+    /// term in 2023/24).
+    pub fn academic_year_start(&self) -> i32 {
+        match self.name {
+            Autumn => self.start.year(),
+            Spring | Summer => self.start.year() - 1,
+        }
+    }
+    /// Returns this term's academic year, like `2023/24`.
+    pub fn academic_year(&self) -> String {
+        let year = self.academic_year_start();
+        format!("{}/{:02}", year, (year + 1) % 100)
+    }
+    /// Returns `s`, where `s <= start` and `s` is the start of a week (a Monday, unless changed
+    /// with `with_week_start`).
+    pub fn loose_start(&self) -> DateTime<Tz> {
+        self.loose_start
+    }
+    /// Returns `e`, where `e >= end` and `e` is the start of a week.
+    pub fn loose_end(&self) -> DateTime<Tz> {
+        self.loose_end
+    }
+    /// Returns how long the term lasts, from `start` to `end`.
+    ///
+    /// This isn't always a whole number of days, since the clocks might change during term.
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+    /// Returns how long the term's weeks last, from `loose_start` to `loose_end`.
+    pub fn teaching_duration(&self) -> Duration {
+        self.loose_end - self.loose_start
+    }
+    /// Returns the week of term that `dt` is in, where week 1 starts at `loose_start` (or week 0,
+    /// with `with_arrivals_week`, or as set by `with_first_week`).
+    ///
+    /// Dates outside the term get week numbers too: the week before week 1 is week 0, and so on.
+    ///
+    /// Weeks run from Monday to Sunday, so a weekend is in the same week as the days before it;
+    /// `with_week_start(Weekday::Sat)` puts it with the days after it instead.
+    ///
+    /// A term that starts partway through a week (like Summer 2020, on the Tuesday after Easter
+    /// Monday) still starts in week 1, because `loose_start` is the start of that week.
+    pub fn week_number(&self, dt: DateTime<Tz>) -> i64 {
+        // Count days rather than subtracting ISO week numbers, which wrap around at the end of
+        // the year (and some years have 53 of them).
+        let days = (dt.date_naive() - self.loose_start().date_naive()).num_days();
+        // Round down, even before the start of term.
+        days.div_euclid(7) + self.first_week()
+    }
+    /// Returns the start of week `n` of term: midnight on its Monday (or whichever day weeks
+    /// start on, with `with_week_start`).
+    ///
+    /// `n` isn't clamped to the weeks of term, so week 12 of a 10-week term is in the vacation
+    /// after it, and week 0 is the week before it.
+    pub fn monday_of_week(&self, n: i64) -> Result<DateTime<Tz>, NoMidnight> {
+        midnight(self.loose_start.date_naive() + Duration::weeks(n - self.first_week()))
+    }
+    /// Returns the week of term that `dt` is in (see `week_number`).
+    pub fn week(&self, dt: DateTime<Tz>) -> Week {
+        Week {
+            academic_year: self.academic_year_start(),
+            term: self.name,
+            number: self.week_number(dt),
+        }
+    }
+    /// The number of the week starting at `loose_start`.
+    fn first_week(&self) -> i64 {
+        self.first_week
+    }
+    /// Returns the weeks that are at least partly in term, as `(week_number, start, end)`, where
+    /// `start` is the first day of the week (usually Monday) and `end` is the instant after its
+    /// last day ends.
+    pub fn weeks(&self) -> impl Iterator<Item = (i64, DateTime<Tz>, DateTime<Tz>)> {
+        self.loose_range()
+            .weeks()
+            .zip(self.first_week()..)
+            .map(|(week, number)| (number, week.start(), week.end()))
+    }
+    /// Returns the number of weeks that are at least partly in term (including any arrivals
+    /// week).
+    pub fn total_weeks(&self) -> i64 {
+        self.loose_range().days() / 7
+    }
+    /// Returns the time from `start` to `end`.
+    pub fn range(&self) -> DateRange {
+        DateRange::new(self.start, self.end)
+    }
+    /// Returns the time from `loose_start` to `loose_end`.
+    pub fn loose_range(&self) -> DateRange {
+        DateRange::new(self.loose_start, self.loose_end)
+    }
+    /// Returns whether `dt` is in term, counting `start` as inside but `end` (the instant after
+    /// the term) as outside.
+    pub fn contains(&self, dt: DateTime<Tz>) -> bool {
+        self.range().contains(dt)
+    }
+    /// Returns whether `dt` is in a week that's at least partly in term, counting `loose_start`
+    /// as inside but `loose_end` as outside.
+    ///
+    /// `loose_end` is the first instant of the week after the term, so it belongs to whatever
+    /// comes next: if another term's weeks start straight away, the Monday midnight between them
+    /// is in week 1 of the next term, not just after the last week of this one.
+    pub fn loose_contains(&self, dt: DateTime<Tz>) -> bool {
+        self.loose_range().contains(dt)
+    }
+}
+
+/// Formats like `Autumn 2023 (Mon 25 Sep – Fri 01 Dec)`.
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({} – {})",
+            self.name.longname(),
+            self.start.year(),
+            self.start.format("%a %d %b"),
+            self.last_day().format("%a %d %b")
+        )
+    }
+}
+
+/// Serializes as its name and the start and end instants, with UTC offsets rather than the
+/// time zone.
+#[cfg(feature = "serde")]
+impl Serialize for Term {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let fixed = |dt: DateTime<Tz>| dt.with_timezone(&dt.offset().fix());
+        let mut term = serializer.serialize_struct("Term", 3)?;
+        term.serialize_field("name", &self.name)?;
+        term.serialize_field("start", &fixed(self.start))?;
+        term.serialize_field("end", &fixed(self.end))?;
+        term.end()
+    }
+}
+
+/// Returns the first day of the week `date` is in, for weeks starting on `week_start`.
+fn start_of_week(mut date: NaiveDate, week_start: Weekday) -> NaiveDate {
+    while date.weekday() != week_start {
+        date -= Duration::days(1);
+    }
+    date
+}
+
+/// Returns the first and last days of the week `dt` is in, for weeks starting on `week_start`
+/// (e.g. Monday and Sunday).
+pub fn week_range(dt: DateTime<Tz>, week_start: Weekday) -> (NaiveDate, NaiveDate) {
+    let first = start_of_week(dt.date_naive(), week_start);
+    (first, first + Duration::days(6))
+}
+
+/// A day that doesn't start at midnight in York, because the clocks went forward then.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NoMidnight(pub NaiveDate);
+
+impl fmt::Display for NoMidnight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "there was no midnight in York on {}", self.0)
+    }
+}
+
+impl Error for NoMidnight {}
+
+/// Returns the first instant of `date` in York, if that's midnight.
+pub fn midnight(date: NaiveDate) -> Result<DateTime<Tz>, NoMidnight> {
+    midnight_in(&London, date)
+}
+
+/// Returns the first instant of `date` in `zone`, if that's midnight.
+fn midnight_in<Z: TimeZone>(zone: &Z, date: NaiveDate) -> Result<DateTime<Z>, NoMidnight> {
+    zone.from_local_datetime(&date.and_time(NaiveTime::MIN))
+        .earliest()
+        .ok_or(NoMidnight(date))
+}
+
+/// Returns the first instant of `date` in York: midnight, or if the clocks went forward then, the
+/// moment they did.
+pub(crate) fn start_of_day(date: NaiveDate) -> DateTime<Tz> {
+    start_of_day_in(&London, date)
+}
+
+fn start_of_day_in<Z: TimeZone>(zone: &Z, date: NaiveDate) -> DateTime<Z> {
+    midnight_in(zone, date).unwrap_or_else(|_| {
+        // The clocks went forward at what would have been midnight by the clocks of the day
+        // before.
+        let local = date.and_time(NaiveTime::MIN);
+        let before = zone.offset_from_utc_datetime(&(local - Duration::days(1)));
+        zone.from_utc_datetime(&(local - before.fix()))
+    })
+}
+
+/// A problem with a term's dates or with a table of terms, naming each term by its name and first
+/// day.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TermError {
+    /// The term starts or ends on a day with no midnight.
+    Date(NoMidnight),
+    /// The term's last day is before its first.
+    EndsBeforeStart((TermName, NaiveDate)),
+    /// The first term starts after the second, but comes before it in the table.
+    OutOfOrder((TermName, NaiveDate), (TermName, NaiveDate)),
+    /// The first term hasn't ended by the time the second one starts.
+    Overlapping((TermName, NaiveDate), (TermName, NaiveDate)),
+}
+
+impl fmt::Display for TermError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let describe = |&(name, start): &(TermName, NaiveDate)| {
+            format!(
+                "{} term starting {}",
+                name.longname(),
+                start.format("%Y-%m-%d")
+            )
+        };
+        match self {
+            TermError::Date(e) => write!(f, "{}", e),
+            TermError::EndsBeforeStart(term) => {
+                write!(f, "{} ends before it starts", describe(term))
+            }
+            TermError::OutOfOrder(a, b) => {
+                write!(f, "{} is listed before {}", describe(a), describe(b))
+            }
+            TermError::Overlapping(a, b) => {
+                write!(f, "{} overlaps {}", describe(a), describe(b))
+            }
+        }
+    }
+}
+
+impl Error for TermError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TermError::Date(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<NoMidnight> for TermError {
+    fn from(e: NoMidnight) -> TermError {
+        TermError::Date(e)
+    }
+}
+
+/// Checks that each of `terms` ends on or after the day it starts, that they're in chronological
+/// order, and that no two of them overlap.
+pub fn validate_terms(terms: &[Term]) -> Result<(), TermError> {
+    let id = |term: &Term| (term.name(), term.start().date_naive());
+    if let Some(term) = terms.iter().find(|term| term.end() <= term.start()) {
+        return Err(TermError::EndsBeforeStart(id(term)));
+    }
+    for pair in terms.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        if a.start() > b.start() {
+            return Err(TermError::OutOfOrder(id(a), id(b)));
+        }
+        if a.end() > b.start() {
+            return Err(TermError::Overlapping(id(a), id(b)));
+        }
+    }
+    Ok(())
+}
+
+/// Something unusual about a term's dates, which may mean they were typed in wrong.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The term is this many weeks long, rather than 9 to 11.
+    Length(i64),
+    /// The term starts on this date, which isn't a Monday.
+    StartDay(NaiveDate),
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::Length(weeks) => write!(
+                f,
+                "{} long (expected 9 to 11 weeks)",
+                format::plural(*weeks, "week")
+            ),
+            Problem::StartDay(date) => write!(f, "starts on a {}", date.format("%A")),
+        }
+    }
+}
+
+/// Looks for anything unusual about `term`'s dates. Unlike `validate_terms`, these don't stop the
+/// term being used.
+pub fn check_term(term: &Term) -> Vec<Problem> {
+    let mut problems = vec![];
+    let weeks = term.total_weeks();
+    if !(9..=11).contains(&weeks) {
+        problems.push(Problem::Length(weeks));
+    }
+    // Summer term sometimes starts on a Tuesday, when the Monday is Easter Monday.
+    let start = term.start().date_naive();
+    let late_start = term.name() == TermName::Summer && start.weekday() == Weekday::Tue;
+    if start.weekday() != Weekday::Mon && !late_start {
+        problems.push(Problem::StartDay(start));
+    }
+    problems
+}
+
+/// A week of a particular term, which sorts chronologically (so Autumn week 10 comes before
+/// Spring week 1, and both come before any week of the next academic year).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Week {
+    /// The calendar year in which the term's academic year starts.
+    pub academic_year: i32,
+    pub term: TermName,
+    pub number: i64,
+}
+
+/// Where `now` falls relative to the term table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WeekInfo {
+    pub term: TermName,
+    pub week: i64,
+    pub date: NaiveDate,
+    pub weekday: Weekday,
+    /// Whether `now` is within the term proper, rather than just in a week that overlaps it.
+    pub strict: bool,
+    /// Whether `now` is in one of the term's reading weeks.
+    pub reading_week: bool,
+}
+
+/// Which term to choose when `now` is in the weeks of two terms at once.
+///
+/// That can only happen if one term ends partway through a week and the next starts later the
+/// same week, on a day that's in neither term (say, the Thursday between a term ending on a
+/// Wednesday and one starting on a Friday).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum MatchPolicy {
+    /// The term that's ending.
+    First,
+    /// The term that's starting.
+    #[default]
+    Last,
+}
+
+/// Finds the term whose weeks `now` is in, preferring the later term if two terms' loose windows
+/// overlap.
+///
+/// `terms` must be sorted and not overlap (see `validate_terms`).
+pub fn get_term(terms: &[Term], now: DateTime<Tz>) -> Option<&Term> {
+    get_term_by(terms, now, MatchPolicy::Last)
+}
+
+/// Finds the term whose weeks `now` is in, choosing between two terms by `policy` if their loose
+/// windows overlap.
+///
+/// `terms` must be sorted and not overlap (see `validate_terms`). Then their loose windows are in
+/// order too, so the candidates are the last few terms whose first week starts no later than
+/// `now`, for as long as their last week hasn't been and gone.
+pub fn get_term_by(terms: &[Term], now: DateTime<Tz>, policy: MatchPolicy) -> Option<&Term> {
+    let i = terms.partition_point(|term| term.loose_start() <= now);
+    let mut candidates = terms[..i]
+        .iter()
+        .rev()
+        .take_while(|term| term.loose_end() > now);
+    let chosen = match policy {
+        MatchPolicy::First => candidates.last(),
+        MatchPolicy::Last => candidates.next(),
+    };
+    #[cfg(feature = "tracing")]
+    if let Some(term) = chosen {
+        tracing::debug!(
+            %now,
+            chosen = %term,
+            loose_start = %term.loose_start(),
+            loose_end = %term.loose_end(),
+            ?policy,
+            "get_term"
+        );
+    } else {
+        tracing::debug!(%now, "get_term: not in any term's weeks");
+    }
+    chosen
+}
+
+/// Finds the term that `now` is in.
+///
+/// `terms` must be sorted and not overlap (see `validate_terms`).
+pub fn get_strict_term(terms: &[Term], now: DateTime<Tz>) -> Option<&Term> {
+    let i = terms.partition_point(|term| term.start() <= now);
+    let candidate = terms[..i].last();
+    #[cfg(feature = "tracing")]
+    if let Some(term) = candidate {
+        tracing::debug!(
+            %now,
+            candidate = %term,
+            start = %term.start(),
+            end = %term.end(),
+            chosen = term.contains(now),
+            "get_strict_term"
+        );
+    } else {
+        tracing::debug!(%now, "get_strict_term: no term starts by now");
+    }
+    candidate.filter(|term| term.contains(now))
+}
+
+/// Returns whether `now` is in term.
+pub fn is_term_time(terms: &[Term], now: DateTime<Tz>) -> bool {
+    get_strict_term(terms, now).is_some()
+}
+
+/// Returns whether `now` is in a week that's at least partly in term.
+pub fn is_teaching_week(terms: &[Term], now: DateTime<Tz>) -> bool {
+    get_term(terms, now).is_some()
+}
+
+/// Finds the first term whose first week starts after `now`.
+pub fn next_term(terms: &[Term], now: DateTime<Tz>) -> Option<&Term> {
+    terms.iter().find(|&term| term.loose_start() > now)
+}
+
+/// Finds the last term whose last week ended by `now`.
+pub fn previous_term(terms: &[Term], now: DateTime<Tz>) -> Option<&Term> {
+    terms.iter().rev().find(|&term| term.loose_end() <= now)
+}
+
+/// Finds the term that ends last, i.e. the furthest into the future the table goes.
+pub fn latest_term(terms: &[Term]) -> Option<&Term> {
+    terms.iter().max_by_key(|term| term.end())
+}
+
+/// A term starting or ending.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Boundary {
+    Start,
+    End,
+}
+
+/// Finds the next time after `now` that a term starts or ends.
+///
+/// This goes by the real start and end of each term, not its weeks.
+pub fn next_boundary(terms: &[Term], now: DateTime<Tz>) -> Option<(&Term, Boundary)> {
+    let term = terms.iter().find(|&term| term.end() > now)?;
+    if term.start() > now {
+        Some((term, Boundary::Start))
+    } else {
+        Some((term, Boundary::End))
+    }
+}
+
+/// Finds the term that starts on `date`, or that ended the day before.
+pub fn boundary_on(terms: &[Term], date: NaiveDate) -> Option<(&Term, Boundary)> {
+    terms.iter().find_map(|term| {
+        if term.start().date_naive() == date {
+            Some((term, Boundary::Start))
+        } else if term.end().date_naive() == date {
+            Some((term, Boundary::End))
+        } else {
+            None
+        }
+    })
+}
+
+/// Works out which vacation `now` is in, if it's between two terms.
+///
+/// The break between Summer term and the next Autumn term (including early September, before the
+/// new academic year starts) is the Summer vacation.
+///
+/// Before the first term or after the last, this returns `None`, since it's not known when the
+/// vacation starts or ends.
+pub fn get_vacation(terms: &[Term], now: DateTime<Tz>) -> Option<Vacation> {
+    if get_term(terms, now).is_some() {
+        return None;
+    }
+    let previous = previous_term(terms, now)?;
+    let next = next_term(terms, now)?;
+    Some(Vacation::between(previous, next))
+}
+
+/// Works out which week of the vacation `now` is in (see `Vacation::week_number`).
+pub fn vacation_week_number(terms: &[Term], now: DateTime<Tz>) -> Option<i64> {
+    get_vacation(terms, now).map(|vacation| vacation.week_number(now))
+}
+
+/// Finds the terms whose weeks are at least partly between `from` and `to` (inclusive), in order.
+///
+/// If `to` is before `from`, the two are swapped.
+pub fn terms_between(terms: &[Term], from: DateTime<Tz>, to: DateTime<Tz>) -> Vec<&Term> {
+    let (from, to) = if to < from { (to, from) } else { (from, to) };
+    terms
+        .iter()
+        .filter(|term| term.loose_start() <= to && from < term.loose_end())
+        .collect()
+}
+
+/// Counts the term weeks that start after `from` and by `to`, skipping vacations, so that two
+/// days in the same term are as many weeks apart as their week numbers.
+///
+/// If `to` is before `from`, the two are swapped.
+pub fn teaching_weeks_between(terms: &[Term], from: DateTime<Tz>, to: DateTime<Tz>) -> i64 {
+    let (from, to) = if to < from { (to, from) } else { (from, to) };
+    terms_between(terms, from, to)
+        .into_iter()
+        .flat_map(|term| term.weeks())
+        .filter(|&(_, start, _)| from < start && start <= to)
+        .count() as i64
+}
+
+/// Guesses the terms of the academic years after the last one in `terms`, up to the one starting
+/// in `through`, from the usual pattern.
+///
+/// Autumn term usually starts on the last Monday in September, Spring term on the first Monday
+/// from the 6th of January, and Summer term 14 weeks after Spring; each lasts 10 weeks. Real term
+/// dates often differ, especially around Easter, so these are only estimates.
+pub fn extrapolate_terms(terms: &[Term], through: i32) -> Result<Vec<Term>, TermError> {
+    let first = match latest_term(terms) {
+        Some(term) => term.academic_year_start() + 1,
+        None => return Ok(vec![]),
+    };
+    let ten_weeks = |start: NaiveDate| (start, start + Duration::days(7 * 9 + 4));
+    let mut estimates = vec![];
+    for year in first..=through {
+        // Past the end of chrono's calendar, there's nothing to guess.
+        let (mut autumn, mut spring) = match (
+            NaiveDate::from_ymd_opt(year, 9, 30),
+            NaiveDate::from_ymd_opt(year + 1, 1, 6),
+        ) {
+            (Some(autumn), Some(spring)) => (autumn, spring),
+            _ => break,
+        };
+        while autumn.weekday() != Weekday::Mon {
+            autumn -= Duration::days(1);
+        }
+        while spring.weekday() != Weekday::Mon {
+            spring += Duration::days(1);
+        }
+        let summer = spring + Duration::weeks(14);
+        for &(name, start) in &[(Autumn, autumn), (Spring, spring), (Summer, summer)] {
+            let (start, end) = ten_weeks(start);
+            estimates.push(Term::new(name, start, end)?);
+        }
+    }
+    Ok(estimates)
+}
+
+/// A date as `(year, month, day)`.
+type Ymd = (i32, u32, u32);
+
+/// The built-in term dates (generated by `build.rs` from `terms.csv`), as the name, first day and
+/// last day of each term.
+const BUILTIN_TERMS: &[(TermName, Ymd, Ymd)] = include!(concat!(env!("OUT_DIR"), "/terms.rs"));
+
+/// The built-in term dates, sorted by start date.
+pub fn builtin_terms() -> Vec<Term> {
+    // <https://www.york.ac.uk/about/term-dates/>
+    let mut terms: Vec<Term> = BUILTIN_TERMS
+        .iter()
+        .map(|&(name, (sy, sm, sd), (ey, em, ed))| {
+            let date =
+                |y, m, d| NaiveDate::from_ymd_opt(y, m, d).expect("build.rs checks the dates");
+            Term::new(name, date(sy, sm, sd), date(ey, em, ed))
+                .expect("built-in term dates are valid")
+        })
+        .collect();
+    terms.sort_unstable_by_key(|term| term.start());
+    terms
+}
+
+/// The built-in term dates as a table, built the first time it's needed.
+pub fn builtin_table() -> &'static TermTable {
+    static TABLE: OnceLock<TermTable> = OnceLock::new();
+    TABLE.get_or_init(|| TermTable::new(builtin_terms()).expect("built-in term dates are valid"))
+}
+
+/// Works out which term and week `now` is in, according to the built-in term dates.
+pub fn current_week(now: DateTime<Tz>) -> Option<WeekInfo> {
+    builtin_table().week(now)
+}
+
+/// Works out which term and week `now` is in.
+pub fn get_week(terms: &[Term], now: DateTime<Tz>) -> Option<WeekInfo> {
+    get_week_by(terms, now, MatchPolicy::Last)
+}
+
+/// Works out which term and week `now` is in, choosing between two terms by `policy` (see
+/// `get_term_by`).
+///
+/// The term `now` is really in always wins, whatever `policy` says.
+pub fn get_week_by(terms: &[Term], now: DateTime<Tz>, policy: MatchPolicy) -> Option<WeekInfo> {
+    let (term, strict) = match get_strict_term(terms, now) {
+        Some(term) => (term, true),
+        None => (get_term_by(terms, now, policy)?, false),
+    };
+    let week = term.week_number(now);
+    Some(WeekInfo {
+        term: term.name(),
+        week,
+        date: now.date_naive(),
+        weekday: now.weekday(),
+        strict,
+        reading_week: term.is_reading_week(week),
+    })
+}
+
+/// Works out which week of the academic year `now` is in, counting on through vacations from
+/// week 1 of Autumn term (so the week after Autumn week 10 is week 11, even in the Christmas
+/// vacation).
+///
+/// Returns `None` before the first Autumn term and after the end of the table, where it's not
+/// known which academic year `now` is in.
+pub fn continuous_week(terms: &[Term], now: DateTime<Tz>) -> Option<i64> {
+    let autumn = terms
+        .iter()
+        .rev()
+        .filter(|term| term.name() == Autumn)
+        .find(|term| term.loose_start() <= now)?;
+    if now >= latest_term(terms)?.loose_end() {
+        return None;
+    }
+    Some(autumn.week_number(now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).expect("test dates are valid")
+    }
+
+    fn term(name: TermName, start: (i32, u32, u32), end: (i32, u32, u32)) -> Term {
+        let (start, end) = (date(start.0, start.1, start.2), date(end.0, end.1, end.2));
+        Term::new(name, start, end).expect("test terms are valid")
+    }
+
+    /// Midday on the given day in York.
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Tz> {
+        midnight(date(year, month, day)).unwrap() + Duration::hours(12)
+    }
+
+    #[test]
+    fn new_matches_builtin_terms() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1));
+        assert!(builtin_terms().contains(&autumn));
+        let york = |month, day| London.with_ymd_and_hms(2023, month, day, 0, 0, 0).unwrap();
+        assert_eq!(autumn.start(), york(9, 25));
+        assert_eq!(autumn.end(), york(12, 2));
+        assert_eq!(autumn.last_day(), date(2023, 12, 1));
+    }
+
+    #[test]
+    fn new_ends_at_the_end_of_the_last_day() {
+        let york = |month, day| midnight(date(2023, month, day)).unwrap();
+        let day = term(Autumn, (2023, 9, 27), (2023, 9, 27));
+        assert_eq!((day.start(), day.end()), (york(9, 27), york(9, 28)));
+        assert_eq!(day.last_day(), date(2023, 9, 27));
+        // The weeks around a midweek term run Monday to Monday.
+        assert_eq!(
+            (day.loose_start(), day.loose_end()),
+            (york(9, 25), york(10, 2))
+        );
+        assert_eq!(day.with_week_start(Weekday::Mon), Ok(day));
+    }
+
+    #[test]
+    fn durations_include_the_clock_change() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1));
+        // The clocks go back on 29 October.
+        let hour = Duration::hours(1);
+        assert_eq!(autumn.duration(), Duration::days(68) + hour);
+        assert_eq!(autumn.teaching_duration(), Duration::weeks(10) + hour);
+        // No clock change in Spring 2024's weeks, which end before 31 March.
+        let spring = term(Spring, (2024, 1, 8), (2024, 3, 15));
+        assert_eq!(spring.duration(), Duration::days(68));
+        assert_eq!(spring.teaching_duration(), Duration::weeks(10));
+    }
+
+    #[test]
+    fn overlapping_terms_are_rejected() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1));
+        let spring = term(Spring, (2023, 11, 27), (2024, 3, 15));
+        let error = validate_terms(&[autumn, spring]).unwrap_err();
+        assert_eq!(
+            error,
+            TermError::Overlapping((Autumn, date(2023, 9, 25)), (Spring, date(2023, 11, 27)))
+        );
+        assert_eq!(
+            error.to_string(),
+            "Autumn term starting 2023-09-25 overlaps Spring term starting 2023-11-27"
+        );
+    }
+
+    #[test]
+    fn out_of_order_terms_are_rejected() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1));
+        let spring = term(Spring, (2024, 1, 8), (2024, 3, 15));
+        assert_eq!(
+            validate_terms(&[spring, autumn]),
+            Err(TermError::OutOfOrder(
+                (Spring, date(2024, 1, 8)),
+                (Autumn, date(2023, 9, 25))
+            ))
+        );
+        assert_eq!(validate_terms(&[autumn, spring]), Ok(()));
+    }
+
+    #[test]
+    fn backwards_terms_are_rejected() {
+        // The typo in the original table, with the wrong year on the end date.
+        let error = Term::new(Spring, date(2025, 1, 6), date(2024, 3, 14)).unwrap_err();
+        assert_eq!(
+            error,
+            TermError::EndsBeforeStart((Spring, date(2025, 1, 6)))
+        );
+        assert_eq!(
+            error.to_string(),
+            "Spring term starting 2025-01-06 ends before it starts"
+        );
+        // A term can be a single day long, though.
+        let day = term(Spring, (2025, 1, 6), (2025, 1, 6));
+        assert_eq!(validate_terms(&[day]), Ok(()));
+        // Any shorter, and the table is rejected, however the term was made.
+        let empty = Term {
+            end: day.start,
+            ..day
+        };
+        assert_eq!(validate_terms(&[empty]), Err(error));
+    }
+
+    #[test]
+    fn extrapolated_terms_follow_on_from_the_real_ones() {
+        let mut terms = builtin_terms();
+        let guesses = extrapolate_terms(&terms, 2031).unwrap();
+        assert_eq!(guesses.len(), 4 * 3);
+        // The last Monday in September 2028, and the first Monday from 6 January 2029.
+        assert_eq!(guesses[0], term(Autumn, (2028, 9, 25), (2028, 12, 1)));
+        assert_eq!(guesses[1], term(Spring, (2029, 1, 8), (2029, 3, 16)));
+        assert_eq!(guesses[2], term(Summer, (2029, 4, 16), (2029, 6, 22)));
+        assert_eq!(guesses[11].academic_year(), "2031/32");
+        terms.extend(guesses);
+        assert_eq!(validate_terms(&terms), Ok(()));
+    }
+
+    #[test]
+    fn builtin_terms_look_normal() {
+        for term in builtin_terms() {
+            assert_eq!(check_term(&term), vec![], "{}", term);
+        }
+    }
+
+    #[test]
+    fn mistyped_term_is_suspicious() {
+        // Typed as starting on the 26th rather than the 25th, and ending a month late.
+        let autumn = term(Autumn, (2023, 9, 26), (2024, 1, 1));
+        let problems = check_term(&autumn);
+        assert_eq!(
+            problems,
+            vec![Problem::Length(15), Problem::StartDay(date(2023, 9, 26))]
+        );
+        assert_eq!(
+            problems[0].to_string(),
+            "15 weeks long (expected 9 to 11 weeks)"
+        );
+        assert_eq!(problems[1].to_string(), "starts on a Tuesday");
+        // Summer term can start on Easter Tuesday.
+        assert_eq!(
+            check_term(&term(Summer, (2020, 4, 14), (2020, 6, 19))),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn builtin_terms_are_valid() {
+        assert_eq!(validate_terms(&builtin_terms()), Ok(()));
+    }
+
+    #[test]
+    fn generated_table_matches_the_old_hardcoded_one() {
+        // The `terms!` table from before `build.rs` read the dates from `terms.csv`.
+        let old = [
+            (Autumn, (2015, 9, 28), (2015, 12, 4)),
+            (Spring, (2016, 1, 11), (2016, 3, 18)),
+            (Summer, (2016, 4, 18), (2016, 6, 24)),
+            (Autumn, (2016, 9, 26), (2016, 12, 2)),
+            (Spring, (2017, 1, 9), (2017, 3, 17)),
+            (Summer, (2017, 4, 18), (2017, 6, 23)),
+            (Autumn, (2017, 9, 25), (2017, 12, 1)),
+            (Spring, (2018, 1, 8), (2018, 3, 16)),
+            (Summer, (2018, 4, 16), (2018, 6, 22)),
+            (Autumn, (2018, 9, 24), (2018, 11, 30)),
+            (Spring, (2019, 1, 7), (2019, 3, 15)),
+            (Summer, (2019, 4, 15), (2019, 6, 21)),
+            (Autumn, (2019, 9, 30), (2019, 12, 6)),
+            (Spring, (2020, 1, 6), (2020, 3, 13)),
+            (Summer, (2020, 4, 14), (2020, 6, 19)),
+            (Autumn, (2020, 9, 28), (2020, 12, 3)),
+            (Spring, (2021, 1, 11), (2021, 3, 19)),
+            (Summer, (2021, 4, 19), (2021, 6, 25)),
+            (Autumn, (2021, 9, 27), (2021, 12, 3)),
+            (Spring, (2022, 1, 10), (2022, 3, 18)),
+            (Summer, (2022, 4, 19), (2022, 6, 24)),
+            (Autumn, (2022, 9, 26), (2022, 12, 2)),
+            (Spring, (2023, 1, 9), (2023, 3, 17)),
+            (Summer, (2023, 4, 17), (2023, 6, 23)),
+            (Autumn, (2023, 9, 25), (2023, 12, 1)),
+            (Spring, (2024, 1, 8), (2024, 3, 15)),
+            (Summer, (2024, 4, 15), (2024, 6, 21)),
+            (Autumn, (2024, 9, 23), (2024, 11, 29)),
+            (Spring, (2025, 1, 6), (2025, 3, 14)),
+            (Summer, (2025, 4, 22), (2025, 6, 27)),
+            (Autumn, (2025, 9, 29), (2025, 12, 5)),
+            (Spring, (2026, 1, 12), (2026, 3, 20)),
+            (Summer, (2026, 4, 20), (2026, 6, 26)),
+            (Autumn, (2026, 9, 28), (2026, 12, 4)),
+            (Spring, (2027, 1, 11), (2027, 3, 19)),
+            (Summer, (2027, 4, 19), (2027, 6, 25)),
+            (Autumn, (2027, 9, 27), (2027, 12, 3)),
+            (Spring, (2028, 1, 10), (2028, 3, 17)),
+            (Summer, (2028, 4, 24), (2028, 6, 30)),
+        ];
+        assert_eq!(BUILTIN_TERMS, &old[..]);
+        let old: Vec<_> = old
+            .iter()
+            .map(|&(name, start, end)| term(name, start, end))
+            .collect();
+        assert_eq!(builtin_terms(), old);
+    }
+
+    #[test]
+    fn week_numbers_carry_on_over_new_year() {
+        // 2020 has 53 ISO weeks, so subtracting ISO week numbers goes wrong twice over here.
+        let spring = term(Spring, (2020, 12, 28), (2021, 3, 5));
+        assert_eq!(spring.week_number(at(2020, 12, 28)), 1);
+        assert_eq!(spring.week_number(at(2021, 1, 3)), 1);
+        assert_eq!(spring.week_number(at(2021, 1, 4)), 2);
+        assert_eq!(spring.week_number(at(2021, 3, 5)), 10);
+        // Christmas vacation weeks in the new year still count on from Autumn term.
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1));
+        assert_eq!(autumn.week_number(at(2023, 12, 31)), 14);
+        assert_eq!(autumn.week_number(at(2024, 1, 1)), 15);
+    }
+
+    #[test]
+    fn spring_2021_counts_from_its_own_monday() {
+        let spring = term(Spring, (2021, 1, 11), (2021, 3, 19));
+        assert!(builtin_terms().contains(&spring));
+        assert_eq!(spring.week_number(at(2021, 1, 11)), 1);
+        assert_eq!(spring.week_number(at(2021, 3, 19)), 10);
+        // Back over the end of 2020 (which has 53 ISO weeks), a week at a time.
+        assert_eq!(spring.week_number(at(2021, 1, 10)), 0);
+        assert_eq!(spring.week_number(at(2021, 1, 3)), -1);
+        assert_eq!(spring.week_number(at(2020, 12, 31)), -1);
+        assert_eq!(spring.week_number(at(2020, 12, 27)), -2);
+        let week = |day| spring.week_number(midnight(day).unwrap() + Duration::hours(12));
+        let mut day = date(2020, 12, 1);
+        while day <= date(2021, 3, 19) {
+            let next = day + Duration::days(1);
+            let step = week(next) - week(day);
+            let expected = if next.weekday() == Weekday::Mon { 1 } else { 0 };
+            assert_eq!(step, expected, "{}", next);
+            day = next;
+        }
+    }
+
+    #[test]
+    fn builtin_terms_end_after_they_start() {
+        // build.rs should have refused to build otherwise.
+        for &(name, start, end) in BUILTIN_TERMS {
+            assert!(start <= end, "{:?} term starting {:?}", name, start);
+        }
+    }
+
+    #[test]
+    fn vacations_are_named_after_the_term_before() {
+        let terms = builtin_terms();
+        let name = |y, m, d| get_vacation(&terms, at(y, m, d)).map(|v| v.name());
+        assert_eq!(name(2023, 12, 25), Some(VacationName::Christmas));
+        assert_eq!(name(2024, 4, 1), Some(VacationName::Easter));
+        assert_eq!(name(2024, 8, 1), Some(VacationName::Summer));
+        assert_eq!(name(2023, 10, 9), None);
+        // Before the first term, it's not known when the vacation started.
+        assert_eq!(
+            get_vacation(&terms, terms[0].start() - Duration::weeks(2)),
+            None
+        );
+    }
+
+    #[test]
+    fn early_september_is_still_the_summer_vacation() {
+        let terms = builtin_terms();
+        let summer = get_vacation(&terms, at(2023, 9, 1)).unwrap();
+        assert_eq!(summer.name(), VacationName::Summer);
+        assert_eq!(summer.start(), midnight(date(2023, 6, 24)).unwrap());
+        assert_eq!(summer.end(), midnight(date(2023, 9, 25)).unwrap());
+        assert_eq!(summer.week_number(at(2023, 9, 1)), 10);
+        // Right up to the start of the new academic year.
+        let name = |y, m, d| get_vacation(&terms, at(y, m, d)).map(|v| v.name());
+        assert_eq!(name(2023, 9, 24), Some(VacationName::Summer));
+        assert_eq!(name(2023, 9, 25), None);
+    }
+
+    #[test]
+    fn contains_counts_the_start_but_not_the_end() {
+        let summer = term(Summer, (2020, 4, 14), (2020, 6, 19));
+        assert!(summer.contains(summer.start()));
+        assert!(!summer.contains(summer.start() - Duration::seconds(1)));
+        assert!(summer.contains(summer.end() - Duration::seconds(1)));
+        assert!(!summer.contains(summer.end()));
+        // Easter Monday is in the term's first week, but not in term.
+        assert!(!summer.contains(at(2020, 4, 13)));
+        assert!(summer.loose_contains(at(2020, 4, 13)));
+        assert!(summer.loose_contains(summer.loose_start()));
+        assert!(!summer.loose_contains(summer.loose_start() - Duration::seconds(1)));
+        // The weekend after the last Friday is still in its week.
+        assert!(summer.loose_contains(at(2020, 6, 21)));
+        assert!(!summer.loose_contains(summer.loose_end()));
+    }
+
+    #[test]
+    fn week_number_counts_from_the_first_monday() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1));
+        assert_eq!(autumn.week_number(autumn.start()), 1);
+        assert_eq!(autumn.week_number(at(2023, 10, 1)), 1);
+        assert_eq!(autumn.week_number(at(2023, 10, 9)), 3);
+        assert_eq!(autumn.week_number(at(2023, 12, 1)), 10);
+        // Weeks before term count down, rounding towards the past.
+        assert_eq!(autumn.week_number(at(2023, 9, 24)), 0);
+        assert_eq!(autumn.week_number(at(2023, 9, 17)), -1);
+        assert_eq!(autumn.week(at(2023, 10, 9)).number, 3);
+    }
+
+    #[test]
+    fn binary_search_agrees_with_a_linear_scan() {
+        let terms = builtin_terms();
+        let first = terms[0].loose_start().date_naive() - Duration::weeks(2);
+        let last = latest_term(&terms).unwrap().loose_end().date_naive() + Duration::weeks(2);
+        let mut day = first;
+        while day <= last {
+            for now in [start_of_day(day), at(day.year(), day.month(), day.day())] {
+                // What the old scan found, with `.filter(..).last()`.
+                let loose = terms.iter().rev().find(|term| term.loose_contains(now));
+                assert_eq!(get_term(&terms, now), loose, "{}", now);
+                let strict = terms.iter().rev().find(|term| term.contains(now));
+                assert_eq!(get_strict_term(&terms, now), strict, "{}", now);
+            }
+            day += Duration::days(1);
+        }
+    }
+
+    #[test]
+    fn weeks_sort_chronologically() {
+        assert!(Autumn < Spring && Spring < Summer);
+        let week = |academic_year, term, number| Week {
+            academic_year,
+            term,
+            number,
+        };
+        let mut weeks = vec![
+            week(2024, Autumn, 1),
+            week(2023, Summer, 1),
+            week(2023, Spring, 1),
+            week(2023, Autumn, 10),
+            week(2023, Autumn, 2),
+            week(2023, Autumn, 1),
+        ];
+        weeks.sort();
+        assert_eq!(
+            weeks,
+            [
+                week(2023, Autumn, 1),
+                week(2023, Autumn, 2),
+                week(2023, Autumn, 10),
+                week(2023, Spring, 1),
+                week(2023, Summer, 1),
+                week(2024, Autumn, 1),
+            ]
+        );
+        // The sort order is the order in which the built-in terms come.
+        let weeks: Vec<_> = builtin_terms()
+            .iter()
+            .map(|term| term.week(term.start()))
+            .collect();
+        assert!(weeks.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    #[cfg(feature = "tz")]
+    fn days_without_a_midnight() {
+        // Brazil's clocks used to go forward at midnight.
+        let zone = chrono_tz::America::Sao_Paulo;
+        let day = date(2018, 11, 4);
+        assert_eq!(midnight_in(&zone, day), Err(NoMidnight(day)));
+        assert_eq!(
+            start_of_day_in(&zone, day),
+            zone.with_ymd_and_hms(2018, 11, 4, 1, 0, 0).unwrap()
+        );
+        let day_before = date(2018, 11, 3);
+        assert_eq!(
+            midnight_in(&zone, day_before),
+            Ok(zone.with_ymd_and_hms(2018, 11, 3, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            NoMidnight(day).to_string(),
+            "there was no midnight in York on 2018-11-04"
+        );
+    }
+
+    #[test]
+    fn york_always_has_a_midnight() {
+        // The clocks change at 1am, so the days they change on still start at midnight.
+        for &(month, day) in &[(3, 26), (10, 29)] {
+            let start = midnight(date(2023, month, day)).unwrap();
+            assert_eq!(start.time(), NaiveTime::MIN);
+            assert_eq!(start_of_day(date(2023, month, day)), start);
+        }
+    }
+
+    #[test]
+    fn academic_years_start_in_autumn() {
+        assert_eq!(
+            term(Autumn, (2023, 9, 25), (2023, 12, 1)).academic_year(),
+            "2023/24"
+        );
+        assert_eq!(
+            term(Spring, (2024, 1, 8), (2024, 3, 15)).academic_year(),
+            "2023/24"
+        );
+        assert_eq!(
+            term(Summer, (2024, 4, 15), (2024, 6, 21)).academic_year(),
+            "2023/24"
+        );
+        // Only the last two digits of the second year.
+        let summer = term(Summer, (2000, 4, 17), (2000, 6, 23));
+        assert_eq!(summer.academic_year_start(), 1999);
+        assert_eq!(summer.academic_year(), "1999/00");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn terms_serialize_with_utc_offsets() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1));
+        assert_eq!(
+            serde_json::to_string(&autumn).unwrap(),
+            r#"{"name":"Autumn","start":"2023-09-25T00:00:00+01:00","end":"2023-12-02T00:00:00Z"}"#
+        );
+        assert_eq!(serde_json::to_string(&Spring).unwrap(), r#""Spring""#);
+    }
+
+    #[test]
+    fn term_names_parse_either_way_ignoring_case() {
+        assert_eq!("Autumn".parse(), Ok(Autumn));
+        assert_eq!("spr".parse(), Ok(Spring));
+        assert_eq!("SUMMER".parse(), Ok(Summer));
+        let error = "Winter".parse::<TermName>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r#""Winter" isn't a term (expected Autumn, Spring, Summer, or Aut, Spr, Sum)"#
+        );
+        assert!("Au".parse::<TermName>().is_err());
+    }
+
+    #[test]
+    fn terms_display_with_their_first_and_last_days() {
+        assert_eq!(
+            term(Autumn, (2023, 9, 25), (2023, 12, 1)).to_string(),
+            "Autumn 2023 (Mon 25 Sep – Fri 01 Dec)"
+        );
+        // Spring and Summer are named by the calendar year they're in.
+        assert_eq!(
+            term(Summer, (2020, 4, 14), (2020, 6, 19)).to_string(),
+            "Summer 2020 (Tue 14 Apr – Fri 19 Jun)"
+        );
+    }
+
+    #[test]
+    fn weeks_cover_the_loose_range() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1));
+        let weeks: Vec<_> = autumn.weeks().collect();
+        assert_eq!(weeks.len(), 10);
+        assert_eq!(autumn.total_weeks(), 10);
+        assert_eq!(weeks[0].1, autumn.loose_start());
+        assert_eq!(weeks[9].2, autumn.loose_end());
+        for (i, &(number, start, end)) in weeks.iter().enumerate() {
+            assert_eq!(number, i as i64 + 1);
+            assert_eq!(start.weekday(), Weekday::Mon);
+            assert_eq!((end.date_naive() - start.date_naive()).num_days(), 7);
+            assert_eq!(autumn.week_number(start), number);
+        }
+        // The clocks went back during week 5.
+        assert_eq!(weeks[4].2 - weeks[4].1, Duration::hours(7 * 24 + 1));
+    }
+
+    #[test]
+    fn week_range_runs_from_the_week_start() {
+        assert_eq!(
+            week_range(at(2023, 10, 11), Weekday::Mon),
+            (date(2023, 10, 9), date(2023, 10, 15))
+        );
+        assert_eq!(
+            week_range(at(2023, 10, 15), Weekday::Mon),
+            (date(2023, 10, 9), date(2023, 10, 15))
+        );
+        assert_eq!(
+            week_range(at(2023, 10, 15), Weekday::Sun),
+            (date(2023, 10, 15), date(2023, 10, 21))
+        );
+        // Across the new year.
+        assert_eq!(
+            week_range(at(2024, 1, 1), Weekday::Sun),
+            (date(2023, 12, 31), date(2024, 1, 6))
+        );
+    }
+
+    #[test]
+    fn weeks_can_start_on_sunday() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1))
+            .with_week_start(Weekday::Sun)
+            .unwrap();
+        assert_eq!(autumn.loose_start(), midnight(date(2023, 9, 24)).unwrap());
+        assert_eq!(autumn.loose_end(), midnight(date(2023, 12, 3)).unwrap());
+        assert_eq!(autumn.week_number(at(2023, 10, 14)), 3);
+        assert_eq!(autumn.week_number(at(2023, 10, 15)), 4);
+        assert_eq!(autumn.monday_of_week(4).unwrap().weekday(), Weekday::Sun);
+    }
+
+    #[test]
+    fn mondays_of_weeks_are_not_clamped() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1));
+        let monday = |y, m, d| Ok(midnight(date(y, m, d)).unwrap());
+        assert_eq!(autumn.monday_of_week(1), monday(2023, 9, 25));
+        assert_eq!(autumn.monday_of_week(5), monday(2023, 10, 23));
+        // Past the end of term, in the Christmas vacation.
+        assert_eq!(autumn.monday_of_week(12), monday(2023, 12, 11));
+        assert_eq!(autumn.monday_of_week(0), monday(2023, 9, 18));
+        // It's the start of the week that week numbers count from.
+        let week = autumn.monday_of_week(5).unwrap();
+        assert_eq!(autumn.week_number(week), 5);
+        assert_eq!(autumn.week_number(week - Duration::seconds(1)), 4);
+    }
+
+    #[test]
+    fn arrivals_week_is_week_zero() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1))
+            .with_arrivals_week()
+            .unwrap();
+        assert_eq!(autumn.loose_start(), midnight(date(2023, 9, 18)).unwrap());
+        assert_eq!(autumn.week_number(at(2023, 9, 18)), 0);
+        // The rest of term keeps its week numbers.
+        assert_eq!(autumn.week_number(at(2023, 10, 9)), 3);
+        assert_eq!(autumn.total_weeks(), 11);
+        assert_eq!(autumn.weeks().next().unwrap().0, 0);
+        // Doing it twice doesn't add another week.
+        assert_eq!(autumn.with_arrivals_week().unwrap(), autumn);
+    }
+
+    #[test]
+    fn builtin_table_is_built_once() {
+        assert!(std::ptr::eq(builtin_table(), builtin_table()));
+        assert_eq!(builtin_table().terms(), &builtin_terms()[..]);
+        let week = current_week(at(2023, 10, 9)).unwrap();
+        assert_eq!((week.term, week.week), (Autumn, 3));
+    }
+
+    #[test]
+    fn next_boundary_goes_by_the_real_dates() {
+        let terms = builtin_terms();
+        let next = |now| next_boundary(&terms, now).map(|(term, b)| (term.start(), b));
+        let autumn = midnight(date(2023, 9, 25)).unwrap();
+        assert_eq!(next(at(2023, 9, 20)), Some((autumn, Boundary::Start)));
+        assert_eq!(next(autumn), Some((autumn, Boundary::End)));
+        assert_eq!(next(at(2023, 12, 1)), Some((autumn, Boundary::End)));
+        let spring = midnight(date(2024, 1, 8)).unwrap();
+        assert_eq!(next(at(2023, 12, 2)), Some((spring, Boundary::Start)));
+        assert_eq!(next(at(2031, 1, 1)), None);
+    }
+
+    #[test]
+    fn boundaries_are_on_the_first_day_and_the_day_after_the_last() {
+        let terms = builtin_terms();
+        let on = |y, m, d| boundary_on(&terms, date(y, m, d)).map(|(term, b)| (term.name(), b));
+        assert_eq!(on(2023, 9, 25), Some((Autumn, Boundary::Start)));
+        assert_eq!(on(2023, 9, 24), None);
+        assert_eq!(on(2023, 9, 26), None);
+        // The last day of term is still in it.
+        assert_eq!(on(2023, 12, 1), None);
+        assert_eq!(on(2023, 12, 2), Some((Autumn, Boundary::End)));
+    }
+
+    #[test]
+    fn shared_monday_goes_to_the_next_term() {
+        // One term's last week ends the instant the next term's first week starts.
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1));
+        let spring = term(Spring, (2023, 12, 4), (2024, 2, 9));
+        assert_eq!(autumn.loose_end(), spring.loose_start());
+        let terms = [autumn, spring];
+        let monday = spring.loose_start();
+        for &policy in &[MatchPolicy::First, MatchPolicy::Last] {
+            assert_eq!(get_term_by(&terms, monday, policy), Some(&spring));
+            assert_eq!(
+                get_term_by(&terms, monday - Duration::seconds(1), policy),
+                Some(&autumn)
+            );
+        }
+    }
+
+    #[test]
+    fn shared_week_goes_by_the_policy() {
+        // Autumn ends on a Wednesday and Spring starts on the Friday, so Thursday is in neither
+        // term, but in a week of both.
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 11, 29));
+        let spring = term(Spring, (2023, 12, 1), (2024, 2, 9));
+        let terms = [autumn, spring];
+        let thursday = at(2023, 11, 30);
+        assert_eq!(
+            get_term_by(&terms, thursday, MatchPolicy::First),
+            Some(&autumn)
+        );
+        assert_eq!(
+            get_term_by(&terms, thursday, MatchPolicy::Last),
+            Some(&spring)
+        );
+        assert_eq!(get_term(&terms, thursday), Some(&spring));
+        // Either way, a day in term is in that term.
+        let wednesday = get_week_by(&terms, at(2023, 11, 29), MatchPolicy::Last).unwrap();
+        assert_eq!((wednesday.term, wednesday.strict), (Autumn, true));
+    }
+
+    #[test]
+    fn continuous_weeks_carry_on_through_vacations() {
+        let terms = builtin_terms();
+        assert_eq!(continuous_week(&terms, at(2023, 10, 9)), Some(3));
+        assert_eq!(continuous_week(&terms, at(2023, 12, 25)), Some(14));
+        assert_eq!(continuous_week(&terms, at(2024, 1, 8)), Some(16));
+        // Early September is still the year before.
+        assert_eq!(continuous_week(&terms, at(2024, 9, 1)), Some(49));
+        assert_eq!(continuous_week(&terms, at(2024, 9, 23)), Some(1));
+        assert_eq!(continuous_week(&terms, at(2014, 1, 1)), None);
+        assert_eq!(continuous_week(&terms, at(2031, 1, 1)), None);
+    }
+
+    #[test]
+    fn terms_between_go_by_the_weeks_of_term() {
+        let terms = builtin_terms();
+        let between = |from, to| {
+            terms_between(&terms, from, to)
+                .into_iter()
+                .map(|term| (term.academic_year_start(), term.name()))
+                .collect::<Vec<_>>()
+        };
+        let crossing = [(2023, Autumn), (2023, Spring)];
+        assert_eq!(between(at(2023, 11, 20), at(2024, 1, 10)), crossing);
+        assert_eq!(between(at(2024, 1, 10), at(2023, 11, 20)), crossing);
+        assert_eq!(between(at(2024, 7, 1), at(2024, 9, 1)), []);
+        assert_eq!(
+            between(at(2023, 6, 1), at(2024, 10, 1)),
+            [
+                (2022, Summer),
+                (2023, Autumn),
+                (2023, Spring),
+                (2023, Summer),
+                (2024, Autumn)
+            ]
+        );
+        // The weekend after term is in its last week, and the start of a week is in it.
+        let spring = midnight(date(2024, 1, 8)).unwrap();
+        assert_eq!(between(at(2023, 12, 2), at(2023, 12, 3)), [(2023, Autumn)]);
+        assert_eq!(between(at(2023, 12, 25), spring), [(2023, Spring)]);
+        assert_eq!(
+            teaching_weeks_between(&terms, at(2023, 10, 9), at(2024, 1, 17)),
+            9
+        );
+    }
+
+    #[test]
+    fn terms_can_start_midweek() {
+        // Summer 2020 started on the Tuesday after Easter Monday.
+        let summer = builtin_terms()
+            .into_iter()
+            .find(|term| term.start().date_naive() == date(2020, 4, 14))
+            .unwrap();
+        assert_eq!(summer.loose_start(), midnight(date(2020, 4, 13)).unwrap());
+        assert_eq!(summer.week_number(summer.start()), 1);
+        assert_eq!(summer.total_weeks(), 10);
+        let wednesday = term(Autumn, (2023, 9, 27), (2023, 12, 1));
+        assert_eq!(wednesday.week_number(wednesday.start()), 1);
+        assert_eq!(wednesday.week_number(at(2023, 10, 2)), 2);
+        assert_eq!(wednesday.total_weeks(), 10);
+        assert_eq!(
+            wednesday.weeks().next().unwrap().1,
+            midnight(date(2023, 9, 25)).unwrap()
+        );
+    }
+
+    #[test]
+    fn reading_weeks_are_marked() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1)).with_reading_weeks(&[6, 12]);
+        // Week 12 isn't in term, so it's dropped.
+        assert_eq!(autumn.reading_weeks().collect::<Vec<_>>(), [6]);
+        assert!(autumn.is_reading_week(6));
+        assert!(!autumn.is_reading_week(7));
+        let week = get_week_by(&[autumn], at(2023, 10, 30), MatchPolicy::Last).unwrap();
+        assert_eq!((week.week, week.reading_week), (6, true));
+        // They stay on the same weeks when the weeks are renumbered.
+        let arrivals = autumn.with_arrivals_week().unwrap();
+        assert_eq!(arrivals.reading_weeks().collect::<Vec<_>>(), [6]);
+        let renumbered = autumn.with_first_week(0);
+        assert_eq!(renumbered.reading_weeks().collect::<Vec<_>>(), [5]);
+    }
+
+    #[test]
+    fn term_time_and_teaching_weeks() {
+        let terms = builtin_terms();
+        // A weekday in term.
+        assert!(is_term_time(&terms, at(2023, 10, 9)));
+        assert!(is_teaching_week(&terms, at(2023, 10, 9)));
+        // The weekend after the last day of term.
+        assert!(!is_term_time(&terms, at(2023, 12, 2)));
+        assert!(is_teaching_week(&terms, at(2023, 12, 2)));
+        // Christmas.
+        assert!(!is_term_time(&terms, at(2023, 12, 25)));
+        assert!(!is_teaching_week(&terms, at(2023, 12, 25)));
+        // Easter Monday, the day before Summer term 2020.
+        assert!(!is_term_time(&terms, at(2020, 4, 13)));
+        assert!(is_teaching_week(&terms, at(2020, 4, 13)));
+    }
+
+    #[test]
+    fn vacation_weeks_start_after_the_last_week_of_term() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1));
+        let spring = term(Spring, (2024, 1, 8), (2024, 3, 15));
+        let christmas = Vacation::between(&autumn, &spring);
+        assert_eq!(christmas.name(), VacationName::Christmas);
+        assert_eq!(christmas.start(), autumn.end());
+        assert_eq!(christmas.end(), spring.start());
+        assert_eq!(christmas.week_number(at(2023, 12, 4)), 1);
+        assert_eq!(christmas.week_number(at(2023, 12, 25)), 4);
+        assert_eq!(christmas.week_number(at(2024, 1, 7)), 5);
+        let terms = [autumn, spring];
+        assert_eq!(vacation_week_number(&terms, at(2023, 12, 25)), Some(4));
+        // The weekend after term is still in its last week.
+        assert_eq!(vacation_week_number(&terms, at(2023, 12, 2)), None);
+    }
+}
@@ -1,170 +1,1296 @@
-use chrono::prelude::*;
-use chrono_tz::{Europe::London, Tz};
-
-macro_rules! term {
-    // "to" the day on which term ends (usually a Friday).
-    ($name:ident, $sy:literal-$sm:literal-$sd:literal to $ey:literal-$em:literal-$ed:literal) => {{
-        let start = London.ymd($sy, $sm, $sd).and_hms(0, 0, 0);
-        let end = London.ymd($ey, $em, $ed).succ().and_hms(0, 0, 0);
-        Term {
-            name: $name,
-            start,
-            end,
-        }
-    }};
-}
-
-macro_rules! terms {
-    ($($name:ident: $sy:literal-$sm:literal-$sd:literal to $ey:literal-$em:literal-$ed:literal),+$(,)?) => {
-        [
-            $(
-                term!($name, $sy-$sm-$sd to $ey-$em-$ed)
-            ),+
-        ]
-    };
+use std::{
+    env,
+    error::Error,
+    io::{self, BufRead, IsTerminal, Write},
+    path::PathBuf,
+    process,
+};
+
+use anstream::{AutoStream, ColorChoice};
+use anstyle::{AnsiColor, Style};
+use chrono::{prelude::*, Duration};
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use serde::Serialize;
+
+#[cfg(feature = "fetch")]
+use uoyweek::fetch;
+use uoyweek::{
+    builtin_terms, check_term, extrapolate_terms, file,
+    format::{self, Format},
+    ical, latest_term, midnight, week_range,
+    when::When,
+    zone::{London, Tz},
+    MatchPolicy, NoMidnight, Term, TermName, TermTable, WeekInfo,
+};
+
+/// The kinds of output other than `--ical` and `--year`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+enum Output {
+    /// `Aut/5/Mon`, or as set by `--format`.
+    #[default]
+    Text,
+    /// Machine-readable JSON.
+    Json,
+    /// JSON for a Waybar custom module.
+    Waybar,
+    /// Just `Aut/5`, without a trailing newline, or nothing out of term.
+    Tmux,
+    /// Just `Aut5`, without a trailing newline, or nothing out of term.
+    Prompt,
+    /// JSON for Slack's `users.profile.set` API.
+    Slack,
+    /// `Aut/5` with polybar color tags, or an empty line out of term.
+    Polybar,
+    /// Just the week number, like `5`.
+    WeekNumber,
+    /// Just the term, like `Aut`.
+    Term,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum TermName {
-    Autumn,
-    Spring,
-    Summer,
+/// Works out which week of term it is at the University of York, like `Aut/5/Mon`.
+#[derive(Debug, Parser)]
+#[command(version, group(ArgGroup::new("output")))]
+struct Options {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// The day (YYYY-MM-DD or DD/MM/YYYY) or time (RFC 3339) to look up, instead of now.
+    #[arg(long, global = true, value_name = "DATE")]
+    date: Option<When>,
+    /// The timezone whose calendar to go by, if not York's.
+    #[cfg(feature = "tz")]
+    #[arg(
+        long,
+        global = true,
+        value_name = "AREA/CITY",
+        value_parser = parse_timezone,
+        conflicts_with = "utc"
+    )]
+    timezone: Option<chrono_tz::Tz>,
+    /// Go by the date in UTC, not York. Terms still start and end at midnight in York, so during
+    /// British Summer Time, the hour after midnight in York counts as the day before.
+    #[arg(long, global = true)]
+    utc: bool,
+    /// The hour of the morning that days (and so weeks) start at, if not midnight, so that with
+    /// `--boundary-hour 6`, 05:00 on a Monday still counts as Sunday.
+    #[arg(
+        long,
+        global = true,
+        value_name = "HOUR",
+        default_value_t = 0,
+        value_parser = parse_boundary_hour
+    )]
+    boundary_hour: u32,
+    /// How many weeks ahead (or, if negative, behind) to look.
+    #[arg(
+        long,
+        value_name = "WEEKS",
+        default_value_t = 0,
+        allow_negative_numbers = true
+    )]
+    offset: i64,
+    /// Print machine-readable JSON.
+    #[arg(long, group = "output")]
+    json: bool,
+    /// Indent `--json` (and `list --json`) output over several lines, for reading.
+    #[arg(long, global = true)]
+    pretty: bool,
+    /// Print JSON for a Waybar custom module.
+    #[arg(long, group = "output")]
+    waybar: bool,
+    /// Print just `Aut/5`, without a trailing newline, or nothing out of term.
+    #[arg(long, group = "output")]
+    tmux: bool,
+    /// Print just `Aut5`, without a trailing newline, or nothing out of term, for a shell prompt.
+    #[arg(long, group = "output")]
+    prompt: bool,
+    /// Print JSON for setting a Slack status, like `{"status_text":"Aut/5","status_emoji":":books:"}`.
+    #[arg(long, group = "output")]
+    slack: bool,
+    /// Print `Aut/5` for a polybar module: green in term, grey in a week that's only partly in
+    /// term, or an empty line (which hides the module) out of term.
+    #[arg(long, group = "output")]
+    polybar: bool,
+    /// Print just the week number, like `5`, or `n/a` (or `--na-text`) out of term.
+    #[arg(long, group = "output")]
+    weeknum_only: bool,
+    /// Print just the term, like `Aut` (or `Autumn` with `--long`), or `n/a` (or `--na-text`)
+    /// out of term.
+    #[arg(long, group = "output")]
+    term_only: bool,
+    /// A TOML file of term dates to use instead of the built-in ones (by default, the file named
+    /// by `UOYWEEK_TERMS`, if set).
+    #[arg(long, global = true, value_name = "PATH")]
+    terms_file: Option<PathBuf>,
+    /// How to lay out the week, like `%t/%w/%a` (%t is the term, %L its long name, %w the week
+    /// number, %a the weekday and %A its long name).
+    #[arg(long, conflicts_with = "long")]
+    format: Option<Format>,
+    /// Zero-pad week numbers to this many digits, like `Aut/05/Mon`.
+    #[arg(
+        long,
+        value_name = "DIGITS",
+        value_parser = parse_pad,
+        allow_negative_numbers = true
+    )]
+    pad: Option<usize>,
+    /// The language to name weekdays in, like `fr_FR` for `Aut/5/lun.` (terms are still named in
+    /// English).
+    #[cfg(feature = "locale")]
+    #[arg(long, value_name = "LOCALE", value_parser = parse_locale)]
+    locale: Option<chrono::Locale>,
+    /// Spell out the term and weekday, like `Autumn/5/Monday`.
+    #[arg(long)]
+    long: bool,
+    /// What to print out of term, instead of `n/a`.
+    #[arg(long, value_name = "TEXT", default_value = "n/a")]
+    na_text: String,
+    /// Don't put parentheses around weeks that are only partly in term.
+    #[arg(long)]
+    no_parens: bool,
+    /// Out of term, say which vacation it is and count down to the next term, instead of
+    /// printing `n/a`.
+    #[arg(long)]
+    vacation: bool,
+    /// List the terms of an academic year.
+    #[arg(long, value_name = "YYYY-YY", value_parser = parse_academic_year)]
+    year: Option<i32>,
+    /// The day weeks start on, if not Monday.
+    #[arg(long, value_name = "mon|sun", value_parser = parse_week_start)]
+    week_start: Option<Weekday>,
+    /// Count Saturday and Sunday as part of the week after, not the week before, so the weekend
+    /// before term is already week 1 (and the weekend after it isn't in term).
+    #[arg(long, conflicts_with = "week_start")]
+    weekend_next: bool,
+    /// Which term a day belongs to when it's in neither term, but in a week of both: the one
+    /// that's ending (`first`) or the one that's starting (`last`).
+    #[arg(
+        long,
+        value_name = "first|last",
+        default_value = "last",
+        value_parser = parse_match_policy
+    )]
+    prefer: MatchPolicy,
+    /// Count the week before Autumn term as week 0.
+    #[arg(long, conflicts_with = "count_from")]
+    week_zero: bool,
+    /// Which week of Autumn term is week 1: the first week of teaching, or the week before,
+    /// when new students arrive.
+    #[arg(long, value_name = "WEEK", default_value = "teaching")]
+    count_from: CountFrom,
+    /// Also print the dates of the week (only for the usual text output).
+    #[arg(long)]
+    range: bool,
+    /// Also say how far through term it is, like `(week 5 of 10, 50% through term)` (only for
+    /// the usual text output).
+    #[arg(long)]
+    progress: bool,
+    /// Print just `yes` in term or `no` otherwise (only for the usual text output).
+    #[arg(long)]
+    check: bool,
+    /// Print how long is left of term instead, like `2 weeks 3 days left in Autumn term`, or how
+    /// long until the next term (only for the usual text output).
+    #[arg(long)]
+    remaining: bool,
+    /// Print how many term weeks (not counting vacations) it's been since this day or time
+    /// instead, or how many there are until it (only for the usual text output).
+    #[arg(long, value_name = "DATE")]
+    since: Option<When>,
+    /// Color the usual text output: green in term, yellow in a week that's partly in term, and
+    /// dim otherwise.
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    color: Color,
+    /// Also give the ISO week, like `Aut/5/Mon (ISO 2023-W43)`, or just `ISO 2023-W52` out of
+    /// term (only for the usual text output).
+    #[arg(long)]
+    iso: bool,
+    /// Start with an emoji for the term or vacation, like `🍂 Aut/5/Mon` (only for the usual text
+    /// output).
+    #[arg(long)]
+    emoji: bool,
+    /// Print the week of the academic year instead, counting on through vacations (only for the
+    /// usual text output).
+    #[arg(long)]
+    continuous: bool,
+    /// Read dates from stdin and describe each one.
+    #[arg(long)]
+    stdin: bool,
+    /// List any terms that don't look like the usual pattern (9 to 11 weeks, starting on a
+    /// Monday, or a Tuesday for Summer term), which might be typos in the term dates.
+    #[arg(long)]
+    verify: bool,
+    /// Past the end of the known term dates, guess them from the usual pattern, and mark the
+    /// answer as estimated (with a trailing `~`, or `"estimated": true` in JSON).
+    #[arg(long)]
+    extrapolate: bool,
+    /// Print every term week as an iCalendar feed.
+    #[arg(long)]
+    ical: bool,
+    /// Serve the current week over HTTP at this address, like `127.0.0.1:8080`: as text at `/`
+    /// and as JSON at `/json`, with an iCalendar feed of term weeks at `/terms.ics` and
+    /// Prometheus metrics at `/metrics`.
+    #[cfg(feature = "serve")]
+    #[arg(long, value_name = "ADDR")]
+    serve: Option<String>,
+    /// Send a desktop notification if a term starts today or ended yesterday, and do nothing
+    /// otherwise.
+    #[cfg(feature = "notify")]
+    #[arg(long)]
+    notify: bool,
+    /// Download term dates from the University's website instead of using the built-in ones.
+    #[cfg(feature = "fetch")]
+    #[arg(long, global = true)]
+    fetch: bool,
 }
-use TermName::*;
 
-impl TermName {
-    fn shortname(&self) -> &'static str {
-        match self {
-            Autumn => "Aut",
-            Spring => "Spr",
-            Summer => "Sum",
+/// When to color the usual text output.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Color {
+    /// Only when printing to a terminal.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Which week of Autumn term to count as week 1.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum CountFrom {
+    /// Freshers' week, before teaching starts.
+    Freshers,
+    /// The first week of teaching.
+    #[default]
+    Teaching,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Say when the next term starts or the current one ends.
+    NextEvent,
+    /// Print the last term before this one (or, in a vacation, the one that just ended).
+    PrevTerm,
+    /// Print the first term after this one (or, in a vacation, the one that's coming up).
+    NextTerm,
+    /// Print every known term, grouped by academic year.
+    #[command(group(ArgGroup::new("output")))]
+    List {
+        /// Print a JSON array of terms instead.
+        #[arg(long, group = "output")]
+        json: bool,
+        /// Print a Markdown table instead.
+        #[arg(long, group = "output")]
+        markdown: bool,
+        /// Print CSV instead.
+        #[arg(long, group = "output")]
+        csv: bool,
+    },
+    /// Print a calendar of a term's weeks, one row per week, with the days out of term in
+    /// parentheses.
+    Calendar {
+        /// Which term, like `spring` or `Spr`.
+        #[arg(long)]
+        term: TermName,
+        /// The year the term starts in.
+        #[arg(long, value_name = "YYYY")]
+        year: i32,
+    },
+    /// Say how many term weeks apart two dates are, not counting vacations.
+    Diff {
+        /// The day (YYYY-MM-DD or DD/MM/YYYY) or time (RFC 3339) to count from.
+        from: When,
+        /// The day or time to count to.
+        to: When,
+    },
+    /// Download the latest term dates from the University's website and save them as a terms
+    /// file, for `--terms-file`.
+    #[cfg(feature = "fetch")]
+    Update {
+        /// Where to save the terms file.
+        #[arg(long, value_name = "PATH")]
+        out: PathBuf,
+        /// Overwrite the file if it already exists.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Describe dates typed in one per line, until end of input. As well as dates, `today` goes
+    /// back to now, and `+N` or `-N` moves N weeks on or back from the last date.
+    Repl,
+    /// Print a completion script for a shell.
+    Completions { shell: Shell },
+}
+
+impl Options {
+    fn output(&self) -> Output {
+        if self.json {
+            Output::Json
+        } else if self.waybar {
+            Output::Waybar
+        } else if self.tmux {
+            Output::Tmux
+        } else if self.prompt {
+            Output::Prompt
+        } else if self.slack {
+            Output::Slack
+        } else if self.polybar {
+            Output::Polybar
+        } else if self.weeknum_only {
+            Output::WeekNumber
+        } else if self.term_only {
+            Output::Term
+        } else {
+            Output::Text
+        }
+    }
+    /// The time to look up: `--date` or the current time, moved by `--offset`.
+    fn now(&self) -> Result<DateTime<Tz>, Box<dyn Error>> {
+        let when = match self.date {
+            Some(when) => when,
+            None => When::Instant(clock()?),
+        };
+        Ok(weeks_later(self.resolve(when)?, self.offset)?)
+    }
+    /// Finds the time in York to look up for `when`, going by the calendar of `--timezone` or
+    /// `--utc` (if given).
+    fn resolve(&self, when: When) -> Result<DateTime<Tz>, NoMidnight> {
+        match when {
+            When::Day(date) => midnight(date),
+            When::Instant(now) => {
+                // Before `--boundary-hour`, it's still the day before.
+                let early = Duration::hours(self.boundary_hour.into());
+                match self.local_time(now) {
+                    Some(local) => with_york_dates(local - early),
+                    None if self.boundary_hour == 0 => {
+                        Ok(London.from_utc_datetime(&now.naive_utc()))
+                    }
+                    None => with_york_dates(now.with_timezone(&London).naive_local() - early),
+                }
+            }
+        }
+    }
+    /// The date and time at `now` in the timezone to go by, if not York's (`--timezone` or
+    /// `--utc`).
+    fn local_time(&self, now: DateTime<Utc>) -> Option<NaiveDateTime> {
+        #[cfg(feature = "tz")]
+        if let Some(tz) = self.timezone {
+            return Some(now.with_timezone(&tz).naive_local());
+        }
+        if self.utc {
+            Some(now.naive_utc())
+        } else {
+            None
         }
     }
-    fn longname(&self) -> &'static str {
-        match self {
-            Autumn => "Autumn",
-            Spring => "Spring",
-            Summer => "Summer",
+    /// Colors `text` to show `status`, if `--color` says to.
+    fn paint(&self, text: &str, status: Status) -> String {
+        let color = match self.color {
+            Color::Always => true,
+            Color::Never => false,
+            Color::Auto => AutoStream::choice(&io::stdout()) != ColorChoice::Never,
+        };
+        if !color {
+            return text.into();
         }
+        let style = match status {
+            Status::Term => Style::new().fg_color(Some(AnsiColor::Green.into())),
+            Status::PartialWeek => Style::new().fg_color(Some(AnsiColor::Yellow.into())),
+            _ => Style::new().dimmed(),
+        };
+        format!("{}{}{}", style.render(), text, style.render_reset())
     }
+    fn format(&self) -> Format {
+        let format = match &self.format {
+            Some(format) => format.clone(),
+            None if self.long => Format::long(),
+            None => Format::default(),
+        };
+        let format = match self.pad {
+            Some(width) => format.with_width(width),
+            None => format,
+        };
+        #[cfg(feature = "locale")]
+        if let Some(locale) = self.locale {
+            return format.with_locale(locale);
+        }
+        format
+    }
+}
+
+/// The JSON for a Waybar custom module.
+#[derive(Debug, Serialize)]
+struct WaybarOutput {
+    text: String,
+    tooltip: String,
+    class: &'static str,
+}
+
+/// The JSON for setting a Slack status with `users.profile.set`.
+#[derive(Debug, Serialize)]
+struct SlackOutput {
+    status_text: String,
+    status_emoji: &'static str,
+}
+
+impl SlackOutput {
+    /// `Aut/5` with books in term, the name of the vacation with a palm tree out of term, or
+    /// nothing (which clears the status) outside the known terms.
+    fn new(table: &TermTable, now: DateTime<Tz>, week: Option<WeekInfo>) -> SlackOutput {
+        match (week, table.vacation(now)) {
+            (Some(week), _) => SlackOutput {
+                status_text: format!("{}/{}", week.term.shortname(), week.week),
+                status_emoji: ":books:",
+            },
+            (None, Some(vacation)) => SlackOutput {
+                status_text: format!("{} vacation", vacation.name().longname()),
+                status_emoji: ":palm_tree:",
+            },
+            (None, None) => SlackOutput {
+                status_text: String::new(),
+                status_emoji: "",
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum JsonOutput {
+    InTerm {
+        term: &'static str,
+        term_short: &'static str,
+        week: i64,
+        weekday: String,
+        in_strict_term: bool,
+        /// Whether `now` is in a week that's only partly in term.
+        partial_week: bool,
+        /// Whether `now` is in a reading week.
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        reading_week: bool,
+        /// Whether the term dates were guessed with `--extrapolate`.
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        estimated: bool,
+    },
+    NotInTerm {
+        in_term: bool,
+        partial_week: bool,
+    },
 }
 
+impl JsonOutput {
+    fn new(week: Option<WeekInfo>, now: DateTime<Tz>, estimated: bool) -> JsonOutput {
+        match week {
+            Some(week) => JsonOutput::InTerm {
+                term: week.term.longname(),
+                term_short: week.term.shortname(),
+                week: week.week,
+                weekday: now.format("%a").to_string(),
+                in_strict_term: week.strict,
+                partial_week: !week.strict,
+                reading_week: week.reading_week,
+                estimated,
+            },
+            None => JsonOutput::NotInTerm {
+                in_term: false,
+                partial_week: false,
+            },
+        }
+    }
+}
+
+/// Parses an academic year like `2023-24`, returning the year it starts in.
+fn parse_academic_year(s: &str) -> Result<i32, String> {
+    let invalid = || format!("invalid academic year {:?} (expected YYYY-YY)", s);
+    let mut parts = s.splitn(2, ['-', '/']);
+    let (start, end) = match (parts.next(), parts.next()) {
+        (Some(start), Some(end)) if start.len() == 4 && end.len() == 2 => (start, end),
+        _ => return Err(invalid()),
+    };
+    let start: i32 = start.parse().map_err(|_| invalid())?;
+    let end: i32 = end.parse().map_err(|_| invalid())?;
+    if (start + 1) % 100 == end {
+        Ok(start)
+    } else {
+        Err(invalid())
+    }
+}
+
+#[cfg(feature = "tz")]
+fn parse_timezone(s: &str) -> Result<chrono_tz::Tz, String> {
+    s.parse().map_err(|_| {
+        format!(
+            "invalid timezone {:?} (expected a name like Europe/London)",
+            s
+        )
+    })
+}
+
+#[cfg(feature = "locale")]
+fn parse_locale(s: &str) -> Result<chrono::Locale, String> {
+    use std::convert::TryFrom;
+
+    chrono::Locale::try_from(s)
+        .map_err(|_| format!("invalid locale {:?} (expected a name like fr_FR)", s))
+}
+
+fn parse_pad(s: &str) -> Result<usize, String> {
+    match s.parse() {
+        Ok(width) if width > 0 => Ok(width),
+        _ => Err(format!(
+            "invalid width {:?} (expected a positive number)",
+            s
+        )),
+    }
+}
+
+fn parse_boundary_hour(s: &str) -> Result<u32, String> {
+    match s.parse() {
+        Ok(hour) if hour < 24 => Ok(hour),
+        _ => Err(format!("invalid hour {:?} (expected 0 to 23)", s)),
+    }
+}
+
+fn parse_match_policy(s: &str) -> Result<MatchPolicy, String> {
+    match s {
+        "first" => Ok(MatchPolicy::First),
+        "last" => Ok(MatchPolicy::Last),
+        _ => Err(format!("invalid policy {:?} (expected first or last)", s)),
+    }
+}
+
+fn parse_week_start(s: &str) -> Result<Weekday, String> {
+    match s {
+        "mon" => Ok(Weekday::Mon),
+        "sun" => Ok(Weekday::Sun),
+        _ => Err(format!("invalid week start {:?} (expected mon or sun)", s)),
+    }
+}
+
+/// Gets the term dates from wherever `options` says to, falling back to the built-in ones.
+fn load_terms(options: &Options) -> Vec<Term> {
+    #[cfg(feature = "fetch")]
+    {
+        if options.fetch {
+            match fetch::fetch() {
+                Ok(terms) => return terms,
+                Err(e) => eprintln!(
+                    "uoyweek: warning: using built-in term dates; couldn't fetch {}: {}",
+                    fetch::TERM_DATES_URL,
+                    e
+                ),
+            }
+            return builtin_terms();
+        }
+    }
+    let path = options
+        .terms_file
+        .clone()
+        .or_else(|| env::var_os("UOYWEEK_TERMS").map(PathBuf::from));
+    match path {
+        Some(path) => file::load(&path).unwrap_or_else(|e| {
+            eprintln!(
+                "uoyweek: warning: using built-in term dates; couldn't load {}: {}",
+                path.display(),
+                e
+            );
+            builtin_terms()
+        }),
+        None => builtin_terms(),
+    }
+}
+
+/// What the exit code says about the date that was looked up.
+///
+/// `--ical`, `--year` and `--stdin` don't look up a single date, so they exit with `Term` (0) on
+/// success. `--verify` exits with `Error` (4) if any terms look wrong.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-struct Term {
-    name: TermName,
-    /// The first instant of the term.
-    start: DateTime<Tz>,
-    /// The instant after the term ends.
-    end: DateTime<Tz>,
+enum Status {
+    /// 0: in term.
+    Term = 0,
+    /// 1: in a week that's only partly in term.
+    PartialWeek = 1,
+    /// 2: in a vacation between two known terms.
+    Vacation = 2,
+    /// 3: before or after all the known terms.
+    Unknown = 3,
+    /// 4: something went wrong, like a bad argument.
+    Error = 4,
+}
+
+fn main() {
+    #[cfg(feature = "tracing")]
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(io::stderr)
+        .init();
+    let options = Options::try_parse().unwrap_or_else(|e| {
+        if !e.use_stderr() {
+            // --help or --version.
+            e.exit();
+        }
+        let _ = e.print();
+        process::exit(Status::Error as i32);
+    });
+    // Exiting doesn't flush stdout, and output doesn't always end with a newline.
+    let result = run(options).and_then(|status| Ok(io::stdout().flush().map(|()| status)?));
+    match result {
+        Ok(status) => process::exit(status as i32),
+        // Whatever was reading the output (like `head`) has seen all it wants.
+        Err(e) if is_broken_pipe(&*e) => process::exit(Status::Term as i32),
+        Err(e) => {
+            eprintln!("uoyweek: {}", e);
+            process::exit(Status::Error as i32);
+        }
+    }
 }
 
-impl Term {
-    fn name(&self) -> TermName {
-        self.name
+/// Returns whether `e` is from writing to a pipe that's been closed.
+fn is_broken_pipe(e: &(dyn Error + 'static)) -> bool {
+    e.downcast_ref::<io::Error>()
+        .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+}
+
+fn run(options: Options) -> Result<Status, Box<dyn Error>> {
+    if let Some(Command::Completions { shell }) = options.command {
+        // Generate the script first, since `generate` panics if it can't write to stdout.
+        let mut script = vec![];
+        clap_complete::generate(shell, &mut Options::command(), "uoyweek", &mut script);
+        io::stdout().lock().write_all(&script)?;
+        return Ok(Status::Term);
+    }
+    #[cfg(feature = "fetch")]
+    if let Some(Command::Update { out, force }) = &options.command {
+        if out.exists() && !force {
+            return Err(format!(
+                "{} already exists (use --force to overwrite it)",
+                out.display()
+            )
+            .into());
+        }
+        let terms = fetch::fetch()
+            .map_err(|e| format!("couldn't fetch {}: {}", fetch::TERM_DATES_URL, e))?;
+        std::fs::write(out, file::to_toml(&terms))
+            .map_err(|e| format!("couldn't write {}: {}", out.display(), e))?;
+        writeln!(
+            io::stdout(),
+            "Wrote {} to {}",
+            format::plural(terms.len() as i64, "term"),
+            out.display()
+        )?;
+        return Ok(Status::Term);
+    }
+    let mut out = io::stdout().lock();
+    let now = options.now()?;
+    let mut terms = load_terms(&options);
+    if terms.is_empty() {
+        // Otherwise every date would look like it was out of term.
+        return Err("no term data loaded".into());
     }
-    fn start(&self) -> DateTime<Tz> {
-        self.start
+    // Only ever guess beyond the end of the real term dates, and remember where they end.
+    let real_end = match latest_term(&terms) {
+        Some(latest) if options.extrapolate && now >= latest.loose_end() => Some(latest.end()),
+        _ => None,
+    };
+    if real_end.is_some() {
+        let guesses = extrapolate_terms(&terms, now.year())?;
+        terms.extend(guesses);
+    }
+    let week_start = if options.weekend_next {
+        // The weekend comes at the start of the week, rather than the end.
+        Weekday::Sat
+    } else {
+        options.week_start.unwrap_or(Weekday::Mon)
+    };
+    if week_start != Weekday::Mon {
+        terms = terms
+            .iter()
+            .map(|term| term.with_week_start(week_start))
+            .collect::<Result<_, _>>()?;
+    }
+    if options.week_zero || options.count_from == CountFrom::Freshers {
+        terms = terms
+            .iter()
+            .map(|term| match (term.name(), options.count_from) {
+                (TermName::Autumn, CountFrom::Freshers) => term
+                    .with_arrivals_week()
+                    .map(|term| term.with_first_week(1)),
+                (TermName::Autumn, CountFrom::Teaching) => term.with_arrivals_week(),
+                _ => Ok(*term),
+            })
+            .collect::<Result<_, _>>()?;
     }
-    fn end(&self) -> DateTime<Tz> {
-        self.end
+    let table = TermTable::new(terms)
+        .map_err(|e| format!("invalid term data: {}", e))?
+        .with_policy(options.prefer);
+    // The answer is only an estimate if it comes from one of the guessed terms.
+    let guessed = |term: &Term| real_end.is_some_and(|end| term.start() >= end);
+    let estimated = match table.current(now) {
+        Some(term) => guessed(term),
+        None => {
+            options.vacation
+                && table.vacation(now).is_some()
+                && table.next(now).is_some_and(guessed)
+        }
+    };
+    if options.verify {
+        return Ok(verify(&table)?);
+    }
+    if options.ical {
+        out.write_all(ical::calendar(table.terms(), clock()?).as_bytes())?;
+        return Ok(Status::Term);
     }
-    /// Returns `s`, where `s <= start && s.weekday() == Mon`.
-    fn loose_start(&self) -> DateTime<Tz> {
-        let mut s = self.start.date();
-        while s.weekday() != Weekday::Mon {
-            s = s.pred();
+    if let Some(year) = options.year {
+        let terms: Vec<_> = table
+            .terms()
+            .iter()
+            .filter(|term| term.academic_year_start() == year)
+            .collect();
+        if terms.is_empty() {
+            return Err(format!("no term dates known for {}-{:02}", year, (year + 1) % 100).into());
+        }
+        for term in terms {
+            writeln!(
+                out,
+                "{}: {} to {}",
+                term.name().longname(),
+                term.start().format("%Y-%m-%d"),
+                term.last_day().format("%Y-%m-%d")
+            )?;
+        }
+        return Ok(Status::Term);
+    }
+    #[cfg(feature = "serve")]
+    if let Some(addr) = &options.serve {
+        return serve(&options, &table, addr);
+    }
+    #[cfg(feature = "notify")]
+    if options.notify {
+        if let Some(text) = format::describe_boundary(&table, now) {
+            notify_rust::Notification::new()
+                .summary("uoyweek")
+                .body(&text)
+                .show()
+                .map_err(|e| format!("couldn't send notification: {}", e))?;
+        }
+        return Ok(Status::Term);
+    }
+    match options.command {
+        Some(Command::NextEvent) => return Ok(next_event(&table, now)?),
+        Some(Command::PrevTerm) => {
+            return Ok(match table.previous(now) {
+                Some(term) => {
+                    writeln!(out, "{}", term)?;
+                    Status::Term
+                }
+                None => {
+                    writeln!(out, "No earlier terms known")?;
+                    Status::Unknown
+                }
+            })
+        }
+        Some(Command::NextTerm) => {
+            return Ok(match table.next(now) {
+                Some(term) => {
+                    writeln!(out, "{}", term)?;
+                    Status::Term
+                }
+                None => {
+                    writeln!(out, "No more terms known")?;
+                    Status::Unknown
+                }
+            })
+        }
+        Some(Command::List {
+            json,
+            markdown,
+            csv,
+        }) => {
+            let output = if json {
+                ListOutput::Json {
+                    pretty: options.pretty,
+                }
+            } else if markdown {
+                ListOutput::Markdown
+            } else if csv {
+                ListOutput::Csv
+            } else {
+                ListOutput::Text
+            };
+            list(&table, output)?;
+            return Ok(Status::Term);
+        }
+        Some(Command::Calendar { term, year }) => {
+            let term = table
+                .terms()
+                .iter()
+                .find(|t| t.name() == term && t.start().year() == year)
+                .ok_or_else(|| format!("no {} term known in {}", term.longname(), year))?;
+            out.write_all(format::calendar(term).as_bytes())?;
+            return Ok(Status::Term);
+        }
+        Some(Command::Repl) => {
+            repl(&options, &table, now)?;
+            return Ok(Status::Term);
+        }
+        Some(Command::Diff { from, to }) => {
+            writeln!(
+                out,
+                "{}",
+                format::diff(&table, options.resolve(from)?, options.resolve(to)?)
+            )?;
+            return Ok(Status::Term);
         }
-        s.and_hms(0, 0, 0)
+        _ => {}
     }
-    /// Returns `e`, where `e >= end && e.weekday() == Mon`.
-    fn loose_end(&self) -> DateTime<Tz> {
-        let mut e = self.end.date();
-        while e.weekday() != Weekday::Mon {
-            e = e.succ();
+    if options.stdin {
+        for line in io::stdin().lock().lines() {
+            let when = line?.trim().parse::<When>().map_err(|e| e.to_string());
+            let now = when.and_then(|when| {
+                options
+                    .resolve(when)
+                    .map_err(|e| e.to_string())
+                    .and_then(|day| weeks_later(day, options.offset))
+            });
+            match now {
+                Ok(now) => {
+                    writeln!(out, "{}", describe(&options, &table, now, table.week(now)))?;
+                }
+                // Carry on, so that each line of output still matches a line of input.
+                Err(e) => writeln!(out, "error: {}", e)?,
+            }
         }
-        e.and_hms(0, 0, 0)
+        return Ok(Status::Term);
     }
+    let week = table.week(now);
+    let status = match week {
+        Some(week) if week.strict => Status::Term,
+        Some(_) => Status::PartialWeek,
+        None if table.vacation(now).is_some() => Status::Vacation,
+        None => Status::Unknown,
+    };
+    if let (None, Some(latest)) = (week, latest_term(table.terms())) {
+        if now >= latest.loose_end() {
+            eprintln!(
+                "uoyweek: term data out of date; latest known term ends {}",
+                latest.last_day().format("%Y-%m-%d")
+            );
+        }
+    }
+    let since = options
+        .since
+        .map(|when| options.resolve(when))
+        .transpose()?;
+    match (options.output(), since) {
+        (Output::Text, Some(since)) => {
+            let weeks = format::plural(table.teaching_weeks_between(since, now), "teaching week");
+            let date = since.format("%Y-%m-%d");
+            if since <= now {
+                writeln!(
+                    out,
+                    "{}",
+                    options.paint(&format!("{} since {}", weeks, date), status)
+                )?;
+            } else {
+                writeln!(
+                    out,
+                    "{}",
+                    options.paint(&format!("{} until {}", weeks, date), status)
+                )?;
+            }
+        }
+        (Output::Text, _) if options.continuous => match table.continuous_week(now) {
+            Some(week) => writeln!(out, "{}", options.paint(&week.to_string(), status))?,
+            None => writeln!(out, "{}", options.paint(&options.na_text, status))?,
+        },
+        (Output::Text, _) if options.check => {
+            let answer = if table.is_term_time(now) { "yes" } else { "no" };
+            writeln!(out, "{}", options.paint(answer, status))?;
+        }
+        (Output::Text, _) if options.remaining => match format::describe_remaining(&table, now) {
+            Some(text) => writeln!(out, "{}", options.paint(&text, status))?,
+            None => writeln!(out, "{}", options.paint(&options.na_text, status))?,
+        },
+        (Output::Text, _) if options.progress => {
+            writeln!(
+                out,
+                "{} {}",
+                options.paint(&describe(&options, &table, now, week), status),
+                format::describe_progress(&table, now)
+            )?;
+        }
+        (Output::Text, _) => {
+            let mut text = describe(&options, &table, now, week);
+            if estimated {
+                text.push('~');
+            }
+            if options.iso {
+                let iso = now.iso_week();
+                let iso = format!("ISO {}-W{:02}", iso.year(), iso.week());
+                text = if week.is_none() && !options.vacation {
+                    iso
+                } else {
+                    format!("{} ({})", text, iso)
+                };
+            }
+            if options.emoji {
+                if let Some(emoji) = emoji(&table, now, week) {
+                    text = format!("{} {}", emoji, text);
+                }
+            }
+            writeln!(out, "{}", options.paint(&text, status))?;
+            if options.range {
+                let (first, last) = week_range(now, week_start);
+                writeln!(out, "{} – {}", first, last)?;
+            }
+        }
+        (Output::Json, _) => writeln!(
+            out,
+            "{}",
+            to_json(&JsonOutput::new(week, now, estimated), options.pretty)
+        )?,
+        (Output::Waybar, _) => {
+            let output = WaybarOutput {
+                text: describe(&options, &table, now, week),
+                tooltip: match week {
+                    Some(week) => format!("{} term, week {}", week.term.longname(), week.week),
+                    None => format::describe_vacation(&table, now)
+                        .unwrap_or_else(|| "Not in term".into()),
+                },
+                class: if week.is_some() { "term" } else { "vacation" },
+            };
+            writeln!(
+                out,
+                "{}",
+                serde_json::to_string(&output).expect("JSON output is serializable")
+            )?;
+        }
+        (Output::Tmux, _) => {
+            // No newline, so that the status line collapses when there's nothing to show.
+            if let Some(week) = week {
+                write!(out, "{}/{}", week.term.shortname(), week.week)?;
+            }
+        }
+        (Output::Prompt, _) => {
+            // Likewise, so that the prompt collapses.
+            if let Some(week) = week {
+                write!(out, "{}{}", week.term.shortname(), week.week)?;
+            }
+        }
+        (Output::Slack, _) => writeln!(
+            out,
+            "{}",
+            serde_json::to_string(&SlackOutput::new(&table, now, week))
+                .expect("JSON output is serializable")
+        )?,
+        (Output::Polybar, _) => match week {
+            Some(week) => writeln!(
+                out,
+                "%{{F{}}}{}/{}%{{F-}}",
+                if week.strict { "#00ff00" } else { "#888888" },
+                week.term.shortname(),
+                week.week
+            )?,
+            None => writeln!(out)?,
+        },
+        (Output::WeekNumber, _) => match week {
+            Some(week) => writeln!(out, "{:01$}", week.week, options.pad.unwrap_or(0))?,
+            None => writeln!(out, "{}", options.na_text)?,
+        },
+        (Output::Term, _) => match week {
+            Some(week) if options.long => writeln!(out, "{}", week.term.longname())?,
+            Some(week) => writeln!(out, "{}", week.term.shortname())?,
+            None => writeln!(out, "{}", options.na_text)?,
+        },
+    }
+    Ok(status)
 }
 
-fn get_term(terms: &[Term], now: DateTime<Tz>) -> Option<&Term> {
-    terms
-        .iter()
-        .filter(|&term| term.loose_start() <= now && now <= term.loose_end())
-        .last()
+/// Answers HTTP requests for the current week until killed.
+#[cfg(feature = "serve")]
+fn serve(options: &Options, table: &TermTable, addr: &str) -> Result<Status, Box<dyn Error>> {
+    use tiny_http::{Header, Response, Server};
+
+    let server = Server::http(addr).map_err(|e| format!("couldn't listen on {}: {}", addr, e))?;
+    eprintln!("uoyweek: listening on http://{}", server.server_addr());
+    for request in server.incoming_requests() {
+        // Look the week up afresh for each request, since the server might run for weeks.
+        let now = options.now()?;
+        let week = table.week(now);
+        // Ignore any query string, like the cache-busting ones calendar apps add.
+        let url = request.url();
+        let path = url.split_once('?').map_or(url, |(path, _)| path);
+        let (status, body, content_type) = match path {
+            "/" => (
+                200,
+                describe(options, table, now, week) + "\n",
+                "text/plain",
+            ),
+            "/json" => (
+                200,
+                serde_json::to_string(&JsonOutput::new(week, now, false))
+                    .expect("JSON output is serializable"),
+                "application/json",
+            ),
+            "/metrics" => (
+                200,
+                format::metrics(table, now),
+                "text/plain; version=0.0.4",
+            ),
+            // The same as `--ical`, for subscribing to from a calendar app.
+            "/terms.ics" => (
+                200,
+                ical::calendar(table.terms(), clock()?),
+                "text/calendar",
+            ),
+            _ => (404, "not found\n".into(), "text/plain"),
+        };
+        let header = Header::from_bytes("Content-Type", format!("{}; charset=utf-8", content_type))
+            .expect("header is valid");
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(header);
+        if let Err(e) = request.respond(response) {
+            eprintln!("uoyweek: warning: couldn't respond to request: {}", e);
+        }
+    }
+    Ok(Status::Term)
 }
 
-fn get_strict_term(terms: &[Term], now: DateTime<Tz>) -> Option<&Term> {
-    terms
-        .iter()
-        .filter(|&term| term.start() <= now && now <= term.end())
-        .last()
+/// The current time, unless overridden by setting `UOYWEEK_NOW` to an RFC 3339 timestamp.
+fn clock() -> Result<DateTime<Utc>, String> {
+    match env::var("UOYWEEK_NOW") {
+        Ok(value) => DateTime::parse_from_rfc3339(&value)
+            .map(|now| now.with_timezone(&Utc))
+            .map_err(|e| format!("invalid UOYWEEK_NOW {:?}: {}", value, e)),
+        Err(_) => Ok(Utc::now()),
+    }
 }
 
-fn main() {
-    // <https://www.york.ac.uk/about/term-dates/>
-    let mut terms = terms!(
-        // 2018-19
-        Autumn: 2018-09-24 to 2018-11-30,
-        Spring: 2019-01-07 to 2019-03-15,
-        Summer: 2019-04-15 to 2019-06-21,
-        // 2019-20
-        Autumn: 2019-09-30 to 2019-12-06,
-        Spring: 2020-01-06 to 2020-03-13,
-        Summer: 2020-04-14 to 2020-06-19,
-        // 2020-21
-        Autumn: 2020-09-28 to 2020-12-03,
-        Spring: 2021-01-11 to 2021-03-19,
-        Summer: 2021-04-19 to 2021-06-25,
-        // 2021-22
-        Autumn: 2021-09-27 to 2021-12-03,
-        Spring: 2022-01-10 to 2022-03-18,
-        Summer: 2022-04-19 to 2022-06-24,
-        // 2022-23
-        Autumn: 2022-09-26 to 2022-12-02,
-        Spring: 2023-01-09 to 2023-03-17,
-        Summer: 2023-04-17 to 2023-06-23,
-        // 2023-24
-        Autumn: 2023-09-25 to 2023-12-01,
-        Spring: 2024-01-08 to 2024-03-15,
-        Summer: 2024-04-15 to 2024-06-21,
-        // 2024-25
-        Autumn: 2024-09-23 to 2024-11-29,
-        Spring: 2025-01-06 to 2024-03-14,
-        Summer: 2025-04-22 to 2025-06-27,
-        // 2025-26
-        Autumn: 2025-09-29 to 2025-12-05,
-        Spring: 2026-01-12 to 2026-03-20,
-        Summer: 2026-04-20 to 2026-06-26,
-        // 2026-27
-        Autumn: 2026-09-28 to 2026-12-04,
-        Spring: 2027-01-11 to 2027-03-19,
-        Summer: 2027-04-19 to 2027-06-25,
-        // 2027-28
-        Autumn: 2027-09-27 to 2027-12-03,
-        Spring: 2028-01-10 to 2028-03-17,
-        Summer: 2028-04-24 to 2028-06-30,
-    );
-    terms.sort_unstable_by_key(|term| term.start());
-    let terms = terms;
-    let now = London.from_utc_datetime(&Utc::now().naive_utc());
-    if let Some(term) = get_term(&terms, now) {
-        let termname = term.name().shortname();
-        // FIXME: this is broken when going between years (e.g. before the first term of the year
-        // starts) -- fix when euclidean_division is stable
-        // (<https://github.com/rust-lang/rust/issues/49048>)
-        // OTOH, it shouldn't actually matter -- there's no term that runs between two years.
-        let weeknum = now.iso_week().week() as i32 - term.start().iso_week().week() as i32 + 1;
-        let day = now.format("%a");
-        if let Some(strict_term) = get_strict_term(&terms, now) {
-            assert_eq!(strict_term, term);
-            // We're in real term.
-            println!("{}/{}/{}", termname, weeknum, day);
-        } else {
-            // We're not in real term (i.e. another part of this week is real term).
-            println!("({}/{}/{})", termname, weeknum, day);
+/// Moves `now` on by `weeks` weeks (or back, if negative), keeping the same time of day even if the
+/// clocks change in between.
+fn weeks_later(now: DateTime<Tz>, weeks: i64) -> Result<DateTime<Tz>, String> {
+    let later = Duration::try_weeks(weeks)
+        .and_then(|offset| now.naive_local().checked_add_signed(offset))
+        .ok_or_else(|| {
+            format!(
+                "can't go {} weeks from {}: that's too far",
+                weeks,
+                now.date_naive()
+            )
+        })?;
+    with_york_dates(later).map_err(|e| e.to_string())
+}
+
+/// Finds the time in York which has the same date and time of day as `local`, so that it's in
+/// the same week as `local` is where it was measured.
+fn with_york_dates(local: NaiveDateTime) -> Result<DateTime<Tz>, NoMidnight> {
+    match London.from_local_datetime(&local).earliest() {
+        Some(now) => Ok(now),
+        // The clocks went forward in York at that time, but it's only the day that matters.
+        None => midnight(local.date()),
+    }
+}
+
+/// Prints the terms in `table` that don't look like the usual pattern, and why.
+fn verify(table: &TermTable) -> io::Result<Status> {
+    let mut out = io::stdout().lock();
+    let mut suspicious = 0;
+    for term in table.terms() {
+        let problems: Vec<_> = check_term(term).iter().map(|p| p.to_string()).collect();
+        if !problems.is_empty() {
+            suspicious += 1;
+            writeln!(out, "{}: {}", term, problems.join(", "))?;
+        }
+    }
+    if suspicious == 0 {
+        writeln!(out, "All {} terms look normal", table.terms().len())?;
+        Ok(Status::Term)
+    } else {
+        Ok(Status::Error)
+    }
+}
+
+/// How `list` prints the terms.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ListOutput {
+    /// Under a heading for each academic year, with how many weeks of term it has.
+    Text,
+    /// A JSON array, indented if `pretty`.
+    Json { pretty: bool },
+    /// A GitHub-flavoured Markdown table.
+    Markdown,
+    /// CSV, with a header row.
+    Csv,
+}
+
+/// Reads dates, `today`, or week offsets like `+2` from stdin, and describes each one, starting
+/// from `now`.
+fn repl(options: &Options, table: &TermTable, now: DateTime<Tz>) -> io::Result<()> {
+    let interactive = io::stdin().is_terminal();
+    let prompt = || {
+        if interactive {
+            eprint!("> ");
         }
+    };
+    let mut out = io::stdout().lock();
+    let mut current = now;
+    prompt();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let input = line.trim();
+        let next = match input {
+            "" => {
+                prompt();
+                continue;
+            }
+            "today" => clock().and_then(|now| {
+                options
+                    .resolve(When::Instant(now))
+                    .map_err(|e| e.to_string())
+            }),
+            _ if input.starts_with(['+', '-']) => match input.parse::<i64>() {
+                Ok(weeks) => weeks_later(current, weeks),
+                Err(_) => Err(format!("invalid number of weeks {:?}", input)),
+            },
+            _ => input
+                .parse::<When>()
+                .map_err(|e| e.to_string())
+                .and_then(|when| options.resolve(when).map_err(|e| e.to_string())),
+        };
+        match next {
+            Ok(next) => {
+                current = next;
+                writeln!(
+                    out,
+                    "{}: {}",
+                    current.format("%Y-%m-%d"),
+                    describe(options, table, current, table.week(current))
+                )?;
+            }
+            // Carry on from the last good date.
+            Err(e) => writeln!(out, "error: {}", e)?,
+        }
+        prompt();
+    }
+    Ok(())
+}
+
+/// Serializes `value` as JSON, all on one line unless `pretty`.
+fn to_json<T: Serialize + ?Sized>(value: &T, pretty: bool) -> String {
+    if pretty {
+        serde_json::to_string_pretty(value)
     } else {
-        // Giving a term date would be nonsensical.
-        println!("n/a");
+        serde_json::to_string(value)
+    }
+    .expect("JSON output is serializable")
+}
+
+/// Prints every term in `table`.
+fn list(table: &TermTable, output: ListOutput) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    match output {
+        ListOutput::Text => {
+            let mut year = None;
+            for term in table.terms() {
+                if year != Some(term.academic_year_start()) {
+                    year = Some(term.academic_year_start());
+                    let weeks = table
+                        .terms()
+                        .iter()
+                        .filter(|other| other.academic_year_start() == term.academic_year_start())
+                        .map(|other| other.total_weeks())
+                        .sum();
+                    writeln!(
+                        out,
+                        "{} ({}):",
+                        term.academic_year(),
+                        format::plural(weeks, "week")
+                    )?;
+                }
+                writeln!(out, "    {}", term)?;
+            }
+        }
+        ListOutput::Json { pretty } => writeln!(out, "{}", to_json(table.terms(), pretty))?,
+        ListOutput::Markdown => {
+            writeln!(out, "| Academic Year | Term | Start | End | Weeks |")?;
+            writeln!(out, "| --- | --- | --- | --- | --- |")?;
+            for term in table.terms() {
+                writeln!(
+                    out,
+                    "| {} | {} | {} | {} | {} |",
+                    term.academic_year(),
+                    term.name().longname(),
+                    term.start().format("%Y-%m-%d"),
+                    term.last_day().format("%Y-%m-%d"),
+                    term.total_weeks()
+                )?;
+            }
+        }
+        ListOutput::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["academic_year", "term", "start", "end", "weeks"])?;
+            for term in table.terms() {
+                writer.write_record([
+                    term.academic_year(),
+                    term.name().longname().to_string(),
+                    term.start().format("%Y-%m-%d").to_string(),
+                    term.last_day().format("%Y-%m-%d").to_string(),
+                    term.total_weeks().to_string(),
+                ])?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Says when the next term after `now` starts, or when the current one ends.
+fn next_event(table: &TermTable, now: DateTime<Tz>) -> io::Result<Status> {
+    let mut out = io::stdout().lock();
+    match format::describe_next_event(table, now) {
+        Some(text) => {
+            writeln!(out, "{}", text)?;
+            Ok(Status::Term)
+        }
+        None => {
+            writeln!(out, "No more terms known")?;
+            Ok(Status::Unknown)
+        }
+    }
+}
+
+/// Describes `now` in the usual way, like `Aut/5/Mon`.
+fn describe(
+    options: &Options,
+    table: &TermTable,
+    now: DateTime<Tz>,
+    week: Option<WeekInfo>,
+) -> String {
+    match week {
+        Some(week) => {
+            let text = if week.strict || options.no_parens {
+                // We're in real term (or we've been asked not to say otherwise).
+                options.format().render(&week)
+            } else {
+                // We're not in real term (i.e. another part of this week is real term).
+                format!("({})", options.format().render(&week))
+            };
+            if week.reading_week {
+                text + " [reading week]"
+            } else {
+                text
+            }
+        }
+        None => match format::describe_vacation(table, now) {
+            Some(text) if options.vacation => text,
+            // Giving a term date would be nonsensical.
+            _ => options.na_text.clone(),
+        },
+    }
+}
+
+/// The emoji for the term or vacation `now` is in, if any.
+fn emoji(table: &TermTable, now: DateTime<Tz>, week: Option<WeekInfo>) -> Option<&'static str> {
+    match week {
+        Some(week) => Some(week.term.emoji()),
+        None => table.vacation(now).map(|vacation| vacation.name().emoji()),
     }
 }
@@ -0,0 +1,117 @@
+//! Spans of time in York, like a term or one of its weeks.
+
+use chrono::{prelude::*, Duration};
+
+use crate::{start_of_day, zone::Tz, Term};
+
+/// The time from `start` up to, but not including, `end`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DateRange {
+    start: DateTime<Tz>,
+    end: DateTime<Tz>,
+}
+
+impl DateRange {
+    /// Makes the range from `start` to `end`, which is empty if `end` isn't after `start`.
+    pub fn new(start: DateTime<Tz>, end: DateTime<Tz>) -> DateRange {
+        DateRange { start, end }
+    }
+    pub fn start(&self) -> DateTime<Tz> {
+        self.start
+    }
+    pub fn end(&self) -> DateTime<Tz> {
+        self.end
+    }
+    /// Returns whether `dt` is in the range, counting `start` as inside but `end` as outside.
+    pub fn contains(&self, dt: DateTime<Tz>) -> bool {
+        self.start <= dt && dt < self.end
+    }
+    /// Returns the number of days from the day `start` is on to the day `end` is on.
+    ///
+    /// This counts calendar days in York, so clock changes don't make a day go missing.
+    pub fn days(&self) -> i64 {
+        (self.end.date_naive() - self.start.date_naive()).num_days()
+    }
+    /// Splits the range into weeks, the first starting at `start` and each of the others at the
+    /// start of the same weekday (midnight, unless the clocks went forward then), with the last
+    /// cut short at `end` if need be.
+    pub fn weeks(&self) -> impl Iterator<Item = DateRange> {
+        let DateRange { start, end } = *self;
+        let first = start.date_naive();
+        let week_start = move |i: i64| match i {
+            0 => start,
+            _ => start_of_day(first + Duration::weeks(i)),
+        };
+        (0..)
+            .map(move |i| DateRange::new(week_start(i), week_start(i + 1).min(end)))
+            .take_while(|week| week.start < week.end)
+    }
+}
+
+/// The time from the start of term to the end, not counting the rest of its first and last
+/// weeks (see `Term::loose_range` for that).
+impl From<&Term> for DateRange {
+    fn from(term: &Term) -> DateRange {
+        term.range()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{midnight, TermName::Autumn};
+
+    fn day(year: i32, month: u32, day: u32) -> DateTime<Tz> {
+        let date = NaiveDate::from_ymd_opt(year, month, day).expect("test dates are valid");
+        midnight(date).unwrap()
+    }
+
+    #[test]
+    fn start_is_inside_but_end_is_not() {
+        let range = DateRange::new(day(2023, 9, 25), day(2023, 12, 2));
+        assert!(range.contains(range.start()));
+        assert!(!range.contains(range.start() - Duration::nanoseconds(1)));
+        assert!(range.contains(range.end() - Duration::nanoseconds(1)));
+        assert!(!range.contains(range.end()));
+        let empty = DateRange::new(day(2023, 9, 25), day(2023, 9, 25));
+        assert!(!empty.contains(empty.start()));
+    }
+
+    #[test]
+    fn days_go_by_the_calendar() {
+        // The clocks go back on 29 October, but that doesn't make an extra day.
+        assert_eq!(
+            DateRange::new(day(2023, 9, 25), day(2023, 12, 2)).days(),
+            68
+        );
+        assert_eq!(DateRange::new(day(2024, 3, 30), day(2024, 4, 1)).days(), 2);
+    }
+
+    #[test]
+    fn weeks_cut_the_last_one_short() {
+        let range = DateRange::new(day(2023, 9, 25), day(2023, 12, 2));
+        let weeks: Vec<_> = range.weeks().collect();
+        assert_eq!(weeks.len(), 10);
+        assert_eq!(weeks[0], DateRange::new(day(2023, 9, 25), day(2023, 10, 2)));
+        assert_eq!(
+            weeks[9],
+            DateRange::new(day(2023, 11, 27), day(2023, 12, 2))
+        );
+        assert_eq!(
+            DateRange::new(range.end(), range.start()).weeks().count(),
+            0
+        );
+    }
+
+    #[test]
+    fn terms_convert_to_their_strict_range() {
+        let date = |d| NaiveDate::from_ymd_opt(2023, 9, d).expect("test dates are valid");
+        let term = Term::new(Autumn, date(27), date(29)).unwrap();
+        let range = DateRange::from(&term);
+        assert_eq!(range, DateRange::new(day(2023, 9, 27), day(2023, 9, 30)));
+        assert_eq!(
+            term.loose_range(),
+            DateRange::new(day(2023, 9, 25), day(2023, 10, 2))
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! A sorted, validated table of terms.
+
+use chrono::prelude::*;
+
+use crate::{
+    boundary_on, continuous_week, get_strict_term, get_term_by, get_vacation, get_week_by,
+    is_teaching_week, is_term_time, next_boundary, next_term, previous_term,
+    teaching_weeks_between, terms_between, validate_terms, zone::Tz, Boundary, MatchPolicy, Term,
+    TermError, Vacation, WeekInfo,
+};
+
+/// A table of terms, sorted by start date, none of which overlap.
+///
+/// The free functions like `get_term` need their `&[Term]` to be like this too, but don't check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermTable {
+    terms: Vec<Term>,
+    policy: MatchPolicy,
+}
+
+impl TermTable {
+    /// Sorts and validates `terms`.
+    pub fn new(mut terms: Vec<Term>) -> Result<TermTable, TermError> {
+        terms.sort_unstable_by_key(|term| term.start());
+        validate_terms(&terms)?;
+        Ok(TermTable {
+            terms,
+            policy: MatchPolicy::default(),
+        })
+    }
+    /// Returns the same table, but choosing between two terms by `policy` when their weeks
+    /// overlap (see `get_term_by`).
+    pub fn with_policy(self, policy: MatchPolicy) -> TermTable {
+        TermTable { policy, ..self }
+    }
+    pub fn terms(&self) -> &[Term] {
+        &self.terms
+    }
+    /// Finds the term whose weeks `now` is in, preferring the one it's really in (see
+    /// `get_term_by`).
+    pub fn current(&self, now: DateTime<Tz>) -> Option<&Term> {
+        self.strict_current(now)
+            .or_else(|| get_term_by(&self.terms, now, self.policy))
+    }
+    /// Finds the term that `now` is in (see `get_strict_term`).
+    pub fn strict_current(&self, now: DateTime<Tz>) -> Option<&Term> {
+        get_strict_term(&self.terms, now)
+    }
+    /// Returns whether `now` is in term.
+    pub fn is_term_time(&self, now: DateTime<Tz>) -> bool {
+        is_term_time(&self.terms, now)
+    }
+    /// Returns whether `now` is in a week that's at least partly in term.
+    pub fn is_teaching_week(&self, now: DateTime<Tz>) -> bool {
+        is_teaching_week(&self.terms, now)
+    }
+    /// Finds the first term whose first week starts after `now`.
+    pub fn next(&self, now: DateTime<Tz>) -> Option<&Term> {
+        next_term(&self.terms, now)
+    }
+    /// Finds the last term whose last week ended by `now`.
+    pub fn previous(&self, now: DateTime<Tz>) -> Option<&Term> {
+        previous_term(&self.terms, now)
+    }
+    /// Finds the next time after `now` that a term starts or ends.
+    pub fn next_boundary(&self, now: DateTime<Tz>) -> Option<(&Term, Boundary)> {
+        next_boundary(&self.terms, now)
+    }
+    /// Finds the term that starts on `date`, or that ended the day before.
+    pub fn boundary_on(&self, date: NaiveDate) -> Option<(&Term, Boundary)> {
+        boundary_on(&self.terms, date)
+    }
+    /// Works out which term and week `now` is in.
+    pub fn week(&self, now: DateTime<Tz>) -> Option<WeekInfo> {
+        get_week_by(&self.terms, now, self.policy)
+    }
+    /// Works out which week of the academic year `now` is in (see `continuous_week`).
+    pub fn continuous_week(&self, now: DateTime<Tz>) -> Option<i64> {
+        continuous_week(&self.terms, now)
+    }
+    /// Finds the terms whose weeks are at least partly between `from` and `to` (see
+    /// `terms_between`).
+    pub fn terms_between(&self, from: DateTime<Tz>, to: DateTime<Tz>) -> Vec<&Term> {
+        terms_between(&self.terms, from, to)
+    }
+    /// Counts the term weeks between `from` and `to` (see `teaching_weeks_between`).
+    pub fn teaching_weeks_between(&self, from: DateTime<Tz>, to: DateTime<Tz>) -> i64 {
+        teaching_weeks_between(&self.terms, from, to)
+    }
+    /// Works out which vacation `now` is in, if it's between two terms.
+    pub fn vacation(&self, now: DateTime<Tz>) -> Option<Vacation> {
+        get_vacation(&self.terms, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        builtin_terms,
+        TermName::{self, *},
+    };
+
+    fn term(name: TermName, start: (i32, u32, u32), end: (i32, u32, u32)) -> Term {
+        let date = |(y, m, d)| NaiveDate::from_ymd_opt(y, m, d).expect("test dates are valid");
+        Term::new(name, date(start), date(end)).expect("test terms are valid")
+    }
+
+    /// Midday on the given day in York.
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Tz> {
+        let date = NaiveDate::from_ymd_opt(year, month, day).expect("test dates are valid");
+        crate::midnight(date).unwrap() + chrono::Duration::hours(12)
+    }
+
+    #[test]
+    fn overlapping_terms_are_rejected_in_any_order() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1));
+        let spring = term(Spring, (2023, 11, 27), (2024, 3, 15));
+        let overlap = TermError::Overlapping(
+            (Autumn, autumn.start().date_naive()),
+            (Spring, spring.start().date_naive()),
+        );
+        assert_eq!(TermTable::new(vec![autumn, spring]), Err(overlap));
+        assert_eq!(TermTable::new(vec![spring, autumn]), Err(overlap));
+    }
+
+    #[test]
+    fn terms_can_follow_straight_on() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1));
+        let spring = term(Spring, (2023, 12, 2), (2024, 3, 15));
+        let table = TermTable::new(vec![spring, autumn]).unwrap();
+        assert_eq!(table.terms(), &[autumn, spring]);
+    }
+
+    #[test]
+    fn builtin_table_is_sorted() {
+        let table = TermTable::new(builtin_terms()).unwrap();
+        assert_eq!(table.terms(), &builtin_terms()[..]);
+    }
+
+    #[test]
+    fn queries_go_by_the_table() {
+        let autumn = term(Autumn, (2023, 9, 25), (2023, 12, 1));
+        let spring = term(Spring, (2024, 1, 8), (2024, 3, 15));
+        let table = TermTable::new(vec![autumn, spring]).unwrap();
+        assert_eq!(table.current(at(2023, 10, 9)), Some(&autumn));
+        // The Saturday after term is still in its last week, but not in term.
+        assert_eq!(table.current(at(2023, 12, 2)), Some(&autumn));
+        assert_eq!(table.strict_current(at(2023, 12, 2)), None);
+        assert!(table.is_teaching_week(at(2023, 12, 2)));
+        assert!(!table.is_term_time(at(2023, 12, 2)));
+        assert_eq!(table.next(at(2023, 12, 25)), Some(&spring));
+        assert_eq!(table.previous(at(2023, 12, 25)), Some(&autumn));
+        assert_eq!(table.next(at(2024, 1, 8)), None);
+        let week = table.week(at(2024, 1, 10)).unwrap();
+        assert_eq!((week.term, week.week, week.strict), (Spring, 1, true));
+    }
+
+    #[test]
+    fn builtin_table_goes_back_to_2015() {
+        let table = TermTable::new(builtin_terms()).unwrap();
+        let week = table.week(at(2016, 2, 10)).unwrap();
+        assert_eq!((week.term, week.week, week.strict), (Spring, 5, true));
+        // Nothing is known before the first term, not even which vacation it is.
+        let first = term(Autumn, (2015, 9, 28), (2015, 12, 4));
+        assert_eq!(table.terms()[0], first);
+        assert_eq!(table.current(at(2015, 9, 21)), None);
+        assert_eq!(table.vacation(at(2015, 9, 21)), None);
+        assert_eq!(table.next(at(2015, 9, 21)), Some(&first));
+        let christmas = table.vacation(at(2015, 12, 25)).unwrap();
+        assert_eq!(christmas.name(), crate::VacationName::Christmas);
+        // The summer before the old start of the table is a vacation now.
+        let summer = table.vacation(at(2018, 8, 1)).unwrap();
+        assert_eq!(summer.name(), crate::VacationName::Summer);
+    }
+}
@@ -0,0 +1,105 @@
+//! Days and times to look up, as typed by a person, like `2023-10-11`, `11/10/2023` or
+//! `2023-10-11T12:00:00+01:00`.
+
+use std::{error::Error, fmt, str::FromStr};
+
+use chrono::prelude::*;
+
+/// A day or a time to look up.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum When {
+    /// The start of a day in York.
+    Day(NaiveDate),
+    /// An exact time.
+    Instant(DateTime<Utc>),
+}
+
+/// A string that isn't a day or time in any of the accepted formats.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWhenError(String);
+
+impl fmt::Display for ParseWhenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid date {:?} (tried YYYY-MM-DD, DD/MM/YYYY and RFC 3339)",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseWhenError {}
+
+impl FromStr for When {
+    type Err = ParseWhenError;
+
+    /// Tries YYYY-MM-DD, then DD/MM/YYYY, then an RFC 3339 timestamp.
+    fn from_str(s: &str) -> Result<When, ParseWhenError> {
+        // `%Y` takes any number of digits, but `10/11/23` is more likely to mean 2023 than 23 AD.
+        let formats = [
+            ("%Y-%m-%d", s.split('-').next()),
+            ("%d/%m/%Y", s.rsplit('/').next()),
+        ];
+        for &(format, year) in &formats {
+            if year.is_some_and(|year| year.len() == 4) {
+                if let Ok(date) = NaiveDate::parse_from_str(s, format) {
+                    return Ok(When::Day(date));
+                }
+            }
+        }
+        DateTime::parse_from_rfc3339(s)
+            .map(|now| When::Instant(now.with_timezone(&Utc)))
+            .map_err(|_| ParseWhenError(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(year: i32, month: u32, day: u32) -> When {
+        When::Day(NaiveDate::from_ymd_opt(year, month, day).expect("test dates are valid"))
+    }
+
+    #[test]
+    fn dates_parse_either_way_round() {
+        assert_eq!("2023-10-11".parse(), Ok(day(2023, 10, 11)));
+        assert_eq!("11/10/2023".parse(), Ok(day(2023, 10, 11)));
+        // Day first, as in the UK.
+        assert_eq!("01/02/2024".parse(), Ok(day(2024, 2, 1)));
+    }
+
+    #[test]
+    fn times_are_converted_to_utc() {
+        assert_eq!(
+            "2023-10-11T12:00:00+01:00".parse(),
+            Ok(When::Instant(
+                Utc.with_ymd_and_hms(2023, 10, 11, 11, 0, 0).unwrap()
+            ))
+        );
+        assert_eq!(
+            "2023-10-11T12:00:00Z".parse(),
+            Ok(When::Instant(
+                Utc.with_ymd_and_hms(2023, 10, 11, 12, 0, 0).unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn other_formats_are_errors() {
+        for s in &[
+            "",
+            "tomorrow",
+            "2023-13-01",
+            "31/02/2023",
+            "10/11/23",
+            "2023-10-11 12:00",
+        ] {
+            assert_eq!(s.parse::<When>(), Err(ParseWhenError(s.to_string())));
+        }
+        assert_eq!(
+            ParseWhenError("tomorrow".into()).to_string(),
+            r#"invalid date "tomorrow" (tried YYYY-MM-DD, DD/MM/YYYY and RFC 3339)"#
+        );
+    }
+}
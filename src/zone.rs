@@ -0,0 +1,128 @@
+//! York's time zone.
+//!
+//! With the `tz` feature (on by default), this is `Europe/London` from `chrono-tz`. Without it,
+//! the offset is worked out from the current rule for British Summer Time instead, which is much
+//! smaller, but wrong for dates before 1996 (or if the rule ever changes).
+
+#[cfg(feature = "tz")]
+pub use chrono_tz::{Europe::London, Tz};
+
+#[cfg(not(feature = "tz"))]
+pub use self::fixed::{London, Tz};
+
+#[cfg(not(feature = "tz"))]
+mod fixed {
+    use chrono::{prelude::*, Duration, LocalResult};
+
+    /// UK time: GMT (+00:00) in winter, and BST (+01:00) from 01:00 UTC on the last Sunday in
+    /// March until 01:00 UTC on the last Sunday in October.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    pub struct Tz;
+
+    /// UK time, named like `chrono_tz::Europe::London` so the two can be swapped.
+    #[allow(non_upper_case_globals)]
+    pub const London: Tz = Tz;
+
+    const GMT: i32 = 0;
+    const BST: i32 = 60 * 60;
+
+    /// Returns 01:00 UTC on the last Sunday of `month` in `year`.
+    fn change(year: i32, month: u32) -> NaiveDateTime {
+        // The last day of the month is the day before the 1st of the next.
+        let mut date = NaiveDate::from_ymd_opt(year, month + 1, 1)
+            .expect("the clocks change in March and October")
+            - Duration::days(1);
+        while date.weekday() != Weekday::Sun {
+            date -= Duration::days(1);
+        }
+        date.and_time(NaiveTime::MIN) + Duration::hours(1)
+    }
+
+    fn offset(secs: i32) -> FixedOffset {
+        FixedOffset::east_opt(secs).expect("GMT and BST are valid offsets")
+    }
+
+    fn offset_at(utc: &NaiveDateTime) -> FixedOffset {
+        let year = utc.year();
+        if change(year, 3) <= *utc && *utc < change(year, 10) {
+            offset(BST)
+        } else {
+            offset(GMT)
+        }
+    }
+
+    impl TimeZone for Tz {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> Tz {
+            Tz
+        }
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            // The clocks never change at midday.
+            self.offset_from_local_datetime(&(local.and_time(NaiveTime::MIN) + Duration::hours(12)))
+        }
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            // Try BST first, so that ambiguous times come out earliest first.
+            let mut offsets = [BST, GMT]
+                .iter()
+                .map(|&secs| offset(secs))
+                .filter(|offset| {
+                    offset_at(&(*local - Duration::seconds(offset.local_minus_utc().into())))
+                        == *offset
+                });
+            match (offsets.next(), offsets.next()) {
+                (Some(first), Some(second)) => LocalResult::Ambiguous(first, second),
+                (Some(offset), None) => LocalResult::Single(offset),
+                _ => LocalResult::None,
+            }
+        }
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            offset_at(&utc.and_time(NaiveTime::MIN))
+        }
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            offset_at(utc)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{prelude::*, LocalResult};
+
+    use super::London;
+
+    /// The UTC offset in York at `utc`, in hours.
+    fn offset_at(utc: (i32, u32, u32, u32, u32)) -> i32 {
+        let (y, m, d, h, min) = utc;
+        let utc = Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap();
+        utc.with_timezone(&London).offset().fix().local_minus_utc() / 3600
+    }
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> LocalResult<i32> {
+        London
+            .with_ymd_and_hms(y, m, d, h, min, 0)
+            .map(|dt| dt.offset().fix().local_minus_utc() / 3600)
+    }
+
+    #[test]
+    fn summer_time_starts_and_ends_at_1am_utc() {
+        assert_eq!(offset_at((2023, 1, 15, 12, 0)), 0);
+        assert_eq!(offset_at((2023, 7, 15, 12, 0)), 1);
+        assert_eq!(offset_at((2023, 3, 26, 0, 59)), 0);
+        assert_eq!(offset_at((2023, 3, 26, 1, 0)), 1);
+        assert_eq!(offset_at((2023, 10, 29, 0, 59)), 1);
+        assert_eq!(offset_at((2023, 10, 29, 1, 0)), 0);
+        // Not the same dates every year.
+        assert_eq!(offset_at((2024, 3, 31, 1, 0)), 1);
+        assert_eq!(offset_at((2024, 10, 27, 1, 0)), 0);
+    }
+
+    #[test]
+    fn clock_changes_skip_and_repeat_an_hour() {
+        assert_eq!(local(2023, 3, 26, 0, 30), LocalResult::Single(0));
+        assert_eq!(local(2023, 3, 26, 1, 30), LocalResult::None);
+        assert_eq!(local(2023, 3, 26, 2, 30), LocalResult::Single(1));
+        assert_eq!(local(2023, 10, 29, 1, 30), LocalResult::Ambiguous(1, 0));
+        assert_eq!(local(2023, 10, 29, 2, 30), LocalResult::Single(0));
+    }
+}
@@ -0,0 +1,1118 @@
+//! Tests that run the `uoyweek` binary, at fixed dates.
+
+use std::{
+    env, fs,
+    io::Write,
+    path::PathBuf,
+    process::{self, Command, Stdio},
+};
+
+/// What a run of `uoyweek` printed, and how it exited.
+#[derive(Debug)]
+struct Run {
+    stdout: String,
+    stderr: String,
+    status: i32,
+}
+
+/// Runs `uoyweek` with `args`.
+fn uoyweek(args: &[&str]) -> Run {
+    uoyweek_with(args, &[], "")
+}
+
+/// Runs `uoyweek` with `args`, the environment variables in `env`, and `stdin` as its input.
+fn uoyweek_with(args: &[&str], env: &[(&str, &str)], stdin: &str) -> Run {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_uoyweek"));
+    // Don't let the environment the tests run in change the answers.
+    command
+        .args(args)
+        .env_remove("UOYWEEK_NOW")
+        .env_remove("UOYWEEK_TERMS")
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = command.spawn().expect("uoyweek runs");
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(stdin.as_bytes())
+        .expect("uoyweek reads its input");
+    let output = child.wait_with_output().expect("uoyweek finishes");
+    Run {
+        stdout: String::from_utf8(output.stdout).expect("output is UTF-8"),
+        stderr: String::from_utf8(output.stderr).expect("output is UTF-8"),
+        status: output.status.code().expect("uoyweek exits normally"),
+    }
+}
+
+/// The terms in `terms.csv`, as rows like `Autumn,2023-09-25,2023-12-01`.
+fn builtin_rows() -> Vec<&'static str> {
+    include_str!("../terms.csv")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .skip(1)
+        .collect()
+}
+
+/// A terms file in the temporary directory, which is deleted when dropped.
+struct TermsFile(PathBuf);
+
+impl TermsFile {
+    /// Writes `toml` to a file whose name includes `name`, which should be unique to the test.
+    fn new(name: &str, toml: &str) -> TermsFile {
+        let path = env::temp_dir().join(format!("uoyweek-{}-{}.toml", name, process::id()));
+        fs::write(&path, toml).expect("temporary directory is writable");
+        TermsFile(path)
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().expect("temporary path is UTF-8")
+    }
+}
+
+impl Drop for TermsFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn offset_moves_by_weeks() {
+    assert_eq!(
+        uoyweek(&["--date", "2023-10-09", "--offset", "3"]).stdout,
+        "Aut/6/Mon\n"
+    );
+    assert_eq!(
+        uoyweek(&["--date", "2023-10-09", "--offset", "-2"]).stdout,
+        "Aut/1/Mon\n"
+    );
+}
+
+#[test]
+fn offset_must_be_a_number() {
+    let run = uoyweek(&["--date", "2023-10-09", "--offset", "three"]);
+    assert_eq!(run.status, 4);
+    assert!(run.stderr.contains("three"), "{}", run.stderr);
+}
+
+#[test]
+fn huge_offset_is_an_error() {
+    let run = uoyweek(&["--date", "2023-10-09", "--offset", "99999999999"]);
+    assert_eq!(run.status, 4);
+    assert_eq!(
+        run.stderr,
+        "uoyweek: can't go 99999999999 weeks from 2023-10-09: that's too far\n"
+    );
+    let run = uoyweek_with(&["repl", "--date", "2023-10-09"], &[], "+99999999999\n+1\n");
+    assert_eq!(
+        run.stdout,
+        "error: can't go 99999999999 weeks from 2023-10-09: that's too far\n\
+         2023-10-16: Aut/4/Mon\n"
+    );
+}
+
+#[test]
+fn extrapolated_terms_are_estimated() {
+    assert_eq!(
+        uoyweek(&["--extrapolate", "--date", "2031-10-09"]).stdout,
+        "Aut/2/Thu~\n"
+    );
+    let run = uoyweek(&["--extrapolate", "--json", "--date", "2031-10-09"]);
+    assert!(run.stdout.contains(r#""estimated":true"#), "{}", run.stdout);
+    assert_eq!(run.status, 0);
+    // Guessing is opt-in.
+    let run = uoyweek(&["--date", "2031-10-09"]);
+    assert_eq!((run.stdout.as_str(), run.status), ("n/a\n", 3));
+}
+
+#[test]
+fn real_terms_are_not_estimated() {
+    assert_eq!(
+        uoyweek(&["--extrapolate", "--date", "2023-10-09"]).stdout,
+        "Aut/3/Mon\n"
+    );
+    // Past the real terms, but not in one of the guessed ones either.
+    assert_eq!(
+        uoyweek(&["--extrapolate", "--date", "2031-12-25"]).stdout,
+        "n/a\n"
+    );
+    let run = uoyweek(&["--extrapolate", "--json", "--date", "2031-12-25"]);
+    assert!(!run.stdout.contains("estimated"), "{}", run.stdout);
+}
+
+#[test]
+fn closed_output_is_not_an_error() {
+    let commands: &[&[&str]] = &[
+        &["list"],
+        &["list", "--markdown"],
+        &["list", "--json"],
+        &["list", "--csv"],
+        &["--ical"],
+        &["completions", "bash"],
+        &["--date", "2023-10-09"],
+        &["--year", "2023-24"],
+        &["--verify"],
+    ];
+    for args in commands {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_uoyweek"))
+            .args(*args)
+            .env_remove("UOYWEEK_TERMS")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("uoyweek runs");
+        // Like `uoyweek list | head -0`.
+        drop(child.stdout.take());
+        let output = child.wait_with_output().expect("uoyweek finishes");
+        assert_eq!(output.status.code(), Some(0), "{:?}", args);
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "", "{:?}", args);
+    }
+}
+
+#[test]
+fn year_lists_its_terms() {
+    let expected = "Autumn: 2023-09-25 to 2023-12-01\n\
+                    Spring: 2024-01-08 to 2024-03-15\n\
+                    Summer: 2024-04-15 to 2024-06-21\n";
+    assert_eq!(uoyweek(&["--year", "2023-24"]).stdout, expected);
+    assert_eq!(uoyweek(&["--year", "2023/24"]).stdout, expected);
+    let run = uoyweek(&["--year", "1999-00"]);
+    assert_eq!(run.status, 4);
+    assert_eq!(run.stderr, "uoyweek: no term dates known for 1999-00\n");
+    let run = uoyweek(&["--year", "2023-25"]);
+    assert_eq!(run.status, 4);
+    assert!(run.stderr.contains("expected YYYY-YY"), "{}", run.stderr);
+}
+
+#[test]
+fn past_the_table_says_so() {
+    let run = uoyweek(&["--date", "2031-01-01"]);
+    assert_eq!(run.stdout, "n/a\n");
+    assert_eq!(
+        run.stderr,
+        "uoyweek: term data out of date; latest known term ends 2028-06-30\n"
+    );
+    assert_eq!(run.status, 3);
+    // Before the table, there's nothing to update.
+    let run = uoyweek(&["--date", "2014-01-01"]);
+    assert_eq!(run.stdout, "n/a\n");
+    assert_eq!(run.stderr, "");
+}
+
+#[test]
+fn empty_terms_file_is_an_error() {
+    for (name, toml) in [("empty", ""), ("no-terms", "term = []\n")] {
+        let file = TermsFile::new(name, toml);
+        let run = uoyweek(&["--terms-file", file.path(), "--date", "2023-10-09"]);
+        assert_eq!(run.stdout, "");
+        assert_eq!(run.stderr, "uoyweek: no term data loaded\n");
+        assert_eq!(run.status, 4);
+    }
+}
+
+#[test]
+fn long_names_are_spelled_out() {
+    assert_eq!(
+        uoyweek(&["--date", "2023-10-09", "--long"]).stdout,
+        "Autumn/3/Monday\n"
+    );
+    assert_eq!(
+        uoyweek(&["--date", "2023-12-02", "--long"]).stdout,
+        "(Autumn/10/Saturday)\n"
+    );
+}
+
+#[test]
+fn unknown_format_token_is_an_error() {
+    let run = uoyweek(&["--date", "2023-10-09", "--format", "%q"]);
+    assert_eq!(run.status, 4);
+    assert_eq!(run.stdout, "");
+    assert!(
+        run.stderr.contains("unknown format token %q"),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn reading_weeks_are_marked() {
+    let file = TermsFile::new(
+        "reading",
+        "[[term]]\nname = \"Autumn\"\nstart = \"2023-09-25\"\nend = \"2023-12-01\"\n\
+         reading_weeks = [6]\n",
+    );
+    let week = |args: &[&str]| {
+        let mut all = vec!["--terms-file", file.path()];
+        all.extend_from_slice(args);
+        uoyweek(&all).stdout
+    };
+    assert_eq!(
+        week(&["--date", "2023-10-30"]),
+        "Aut/6/Mon [reading week]\n"
+    );
+    assert!(week(&["--date", "2023-10-30", "--json"]).contains("\"reading_week\":true"));
+    assert_eq!(week(&["--date", "2023-11-06"]), "Aut/7/Mon\n");
+    assert!(!week(&["--date", "2023-11-06", "--json"]).contains("reading_week"));
+}
+
+#[test]
+fn single_fields_can_be_printed() {
+    let field = |flag, date| uoyweek(&["--date", date, flag]).stdout;
+    assert_eq!(field("--weeknum-only", "2023-10-09"), "3\n");
+    assert_eq!(field("--weeknum-only", "2023-12-02"), "10\n");
+    assert_eq!(field("--weeknum-only", "2023-12-25"), "n/a\n");
+    assert_eq!(field("--term-only", "2023-10-09"), "Aut\n");
+    assert_eq!(field("--term-only", "2023-12-25"), "n/a\n");
+    for conflict in [
+        ["--weeknum-only", "--term-only"],
+        ["--weeknum-only", "--json"],
+    ] {
+        let run = uoyweek(&conflict);
+        assert_eq!(run.status, 4);
+        assert!(run.stderr.contains("cannot be used with"), "{}", run.stderr);
+    }
+}
+
+#[cfg(feature = "locale")]
+#[test]
+fn locale_names_the_weekday() {
+    assert_eq!(
+        uoyweek(&["--date", "2023-10-09", "--locale", "fr_FR"]).stdout,
+        "Aut/3/lun.\n"
+    );
+    let run = uoyweek(&["--date", "2023-10-09", "--locale", "xx"]);
+    assert_eq!(run.status, 4);
+    assert!(
+        run.stderr.contains("invalid locale \"xx\""),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn range_prints_the_week() {
+    assert_eq!(
+        uoyweek(&["--date", "2023-12-02", "--range"]).stdout,
+        "(Aut/10/Sat)\n2023-11-27 – 2023-12-03\n"
+    );
+}
+
+#[test]
+fn iso_adds_the_iso_week() {
+    let iso = |date| uoyweek(&["--date", date, "--iso"]).stdout;
+    assert_eq!(iso("2023-11-06"), "Aut/7/Mon (ISO 2023-W45)\n");
+    assert_eq!(iso("2023-12-02"), "(Aut/10/Sat) (ISO 2023-W48)\n");
+    // Out of term there's only the ISO week, which might be in the next ISO year.
+    assert_eq!(iso("2023-12-25"), "ISO 2023-W52\n");
+    assert_eq!(iso("2024-12-30"), "ISO 2025-W01\n");
+}
+
+#[test]
+fn sunday_starts_the_next_week() {
+    let sunday = |date| uoyweek(&["--date", date, "--week-start", "sun", "--range"]).stdout;
+    assert_eq!(sunday("2023-10-14"), "Aut/3/Sat\n2023-10-08 – 2023-10-14\n");
+    assert_eq!(sunday("2023-10-15"), "Aut/4/Sun\n2023-10-15 – 2023-10-21\n");
+    assert_eq!(
+        sunday("2023-09-24"),
+        "(Aut/1/Sun)\n2023-09-24 – 2023-09-30\n"
+    );
+}
+
+#[test]
+fn weekend_next_moves_the_weekend_on_a_week() {
+    let week = |args: &[&str]| {
+        let mut all = vec!["--date", "2023-10-14"];
+        all.extend_from_slice(args);
+        uoyweek(&all).stdout
+    };
+    assert_eq!(week(&[]), "Aut/3/Sat\n");
+    assert_eq!(week(&["--weekend-next"]), "Aut/4/Sat\n");
+    // So the weekend before term is in week 1, and the one after is in the vacation.
+    assert_eq!(
+        uoyweek(&["--date", "2023-09-23", "--weekend-next"]).stdout,
+        "(Aut/1/Sat)\n"
+    );
+    let run = uoyweek(&["--date", "2023-12-02", "--weekend-next"]);
+    assert_eq!((run.stdout.as_str(), run.status), ("n/a\n", 2));
+}
+
+#[test]
+fn known_dates_print_exactly() {
+    let cases = [
+        ("2023-10-09", "Aut/3/Mon"),
+        ("2023-12-02", "(Aut/10/Sat)"),
+        ("2023-12-25", "n/a"),
+        ("2024-01-08", "Spr/1/Mon"),
+        ("2024-03-16", "(Spr/10/Sat)"),
+        ("2024-04-15", "Sum/1/Mon"),
+        ("2024-06-21", "Sum/10/Fri"),
+        ("2024-08-01", "n/a"),
+    ];
+    for (date, expected) in cases {
+        assert_eq!(
+            uoyweek(&["--date", date]).stdout,
+            format!("{}\n", expected),
+            "{}",
+            date
+        );
+    }
+}
+
+#[test]
+fn exit_status_says_where_the_date_is() {
+    let status = |date| uoyweek(&["--date", date]).status;
+    assert_eq!(status("2023-10-09"), 0);
+    assert_eq!(status("2023-12-02"), 1);
+    assert_eq!(status("2023-12-25"), 2);
+    assert_eq!(status("2014-01-01"), 3);
+    assert_eq!(status("2031-01-01"), 3);
+    assert_eq!(status("not a date"), 4);
+    // The other outputs exit the same way.
+    assert_eq!(uoyweek(&["--date", "2023-12-25", "--json"]).status, 2);
+}
+
+#[test]
+fn json_flags_partial_weeks() {
+    let json = |date| uoyweek(&["--date", date, "--json"]).stdout;
+    assert_eq!(
+        json("2023-12-02"),
+        "{\"term\":\"Autumn\",\"term_short\":\"Aut\",\"week\":10,\"weekday\":\"Sat\",\
+         \"in_strict_term\":false,\"partial_week\":true}\n"
+    );
+    assert!(json("2023-10-09").contains("\"in_strict_term\":true,\"partial_week\":false"));
+    assert_eq!(
+        json("2023-12-25"),
+        "{\"in_term\":false,\"partial_week\":false}\n"
+    );
+}
+
+#[test]
+fn pretty_json_is_the_same_json_indented() {
+    for args in [
+        &["--date", "2023-10-09", "--json"][..],
+        &["--date", "2023-12-25", "--json"],
+        &["list", "--json"],
+    ] {
+        let compact = uoyweek(args).stdout;
+        let pretty = uoyweek(&[args, &["--pretty"]].concat()).stdout;
+        assert_eq!(compact.trim_end().lines().count(), 1, "{}", compact);
+        assert!(pretty.trim_end().lines().count() > 1, "{}", pretty);
+        let parse = |json: &str| serde_json::from_str::<serde_json::Value>(json).unwrap();
+        assert_eq!(parse(&pretty), parse(&compact));
+    }
+}
+
+#[test]
+fn week_zero_is_the_week_before_autumn() {
+    assert_eq!(
+        uoyweek(&["--date", "2023-09-18", "--week-zero"]).stdout,
+        "(Aut/0/Mon)\n"
+    );
+    assert_eq!(
+        uoyweek(&["--date", "2023-10-09", "--week-zero"]).stdout,
+        "Aut/3/Mon\n"
+    );
+    assert_eq!(uoyweek(&["--date", "2023-09-18"]).stdout, "n/a\n");
+}
+
+#[test]
+fn count_from_freshers_shifts_every_week() {
+    for (date, teaching, freshers) in [
+        ("2023-09-18", "n/a\n", "(Aut/1/Mon)\n"),
+        ("2023-09-25", "Aut/1/Mon\n", "Aut/2/Mon\n"),
+        ("2023-10-09", "Aut/3/Mon\n", "Aut/4/Mon\n"),
+        ("2023-12-01", "Aut/10/Fri\n", "Aut/11/Fri\n"),
+    ] {
+        let count_from = |origin| uoyweek(&["--date", date, "--count-from", origin]).stdout;
+        assert_eq!(uoyweek(&["--date", date]).stdout, teaching, "{}", date);
+        assert_eq!(count_from("teaching"), teaching, "{}", date);
+        assert_eq!(count_from("freshers"), freshers, "{}", date);
+    }
+    assert_eq!(uoyweek(&["--count-from", "bogus"]).status, 4);
+}
+
+#[test]
+fn prefer_picks_between_terms_sharing_a_week() {
+    // Autumn ends on a Wednesday and Spring starts on the Friday.
+    let file = TermsFile::new(
+        "prefer",
+        "[[term]]\nname = \"Autumn\"\nstart = \"2023-09-25\"\nend = \"2023-11-29\"\n\n\
+         [[term]]\nname = \"Spring\"\nstart = \"2023-12-01\"\nend = \"2024-02-09\"\n",
+    );
+    let thursday = |args: &[&str]| {
+        let mut all = vec!["--terms-file", file.path(), "--date", "2023-11-30"];
+        all.extend_from_slice(args);
+        uoyweek(&all).stdout
+    };
+    assert_eq!(thursday(&[]), "(Spr/1/Thu)\n");
+    assert_eq!(thursday(&["--prefer", "last"]), "(Spr/1/Thu)\n");
+    assert_eq!(thursday(&["--prefer", "first"]), "(Aut/10/Thu)\n");
+    assert_eq!(uoyweek(&["--prefer", "middle"]).status, 4);
+}
+
+#[test]
+fn waybar_gets_text_tooltip_and_class() {
+    assert_eq!(
+        uoyweek(&["--date", "2023-10-09", "--waybar"]).stdout,
+        "{\"text\":\"Aut/3/Mon\",\"tooltip\":\"Autumn term, week 3\",\"class\":\"term\"}\n"
+    );
+    assert_eq!(
+        uoyweek(&["--date", "2023-12-25", "--waybar"]).stdout,
+        "{\"text\":\"n/a\",\"tooltip\":\"Christmas vacation week 4, 2 weeks until Spring\",\
+         \"class\":\"vacation\"}\n"
+    );
+}
+
+#[test]
+fn slack_emoji_changes_in_the_vacation() {
+    assert_eq!(
+        uoyweek(&["--date", "2023-10-09", "--slack"]).stdout,
+        "{\"status_text\":\"Aut/3\",\"status_emoji\":\":books:\"}\n"
+    );
+    assert_eq!(
+        uoyweek(&["--date", "2023-12-25", "--slack"]).stdout,
+        "{\"status_text\":\"Christmas vacation\",\"status_emoji\":\":palm_tree:\"}\n"
+    );
+    // Still JSON, even when there's nothing to say.
+    assert_eq!(
+        uoyweek(&["--date", "2031-01-01", "--slack"]).stdout,
+        "{\"status_text\":\"\",\"status_emoji\":\"\"}\n"
+    );
+}
+
+#[test]
+fn polybar_wraps_the_week_in_color_tags() {
+    let polybar = |date| uoyweek(&["--date", date, "--polybar"]).stdout;
+    assert_eq!(polybar("2023-10-09"), "%{F#00ff00}Aut/3%{F-}\n");
+    // Dimmed at the weekend after term.
+    assert_eq!(polybar("2023-12-02"), "%{F#888888}Aut/10%{F-}\n");
+    // Blank in the vacation, so the module hides.
+    assert_eq!(polybar("2023-12-25"), "\n");
+}
+
+#[test]
+fn emoji_follows_the_season() {
+    let emoji = |date| uoyweek(&["--date", date, "--emoji"]).stdout;
+    assert_eq!(emoji("2023-10-09"), "🍂 Aut/3/Mon\n");
+    assert_eq!(emoji("2023-12-25"), "🎄 n/a\n");
+    assert_eq!(emoji("2024-03-25"), "🐣 n/a\n");
+    // Off by default, and with nothing known there's no season to show.
+    assert_eq!(uoyweek(&["--date", "2023-10-09"]).stdout, "Aut/3/Mon\n");
+    assert_eq!(emoji("2031-01-01"), "n/a\n");
+}
+
+#[test]
+fn diff_counts_teaching_weeks() {
+    let run = uoyweek(&["diff", "2024-01-15", "2024-05-06"]);
+    assert_eq!(
+        run.stdout,
+        "12 teaching weeks apart, crossing Spring and Summer terms\n"
+    );
+    assert_eq!(run.status, 0);
+    assert_eq!(
+        uoyweek(&["diff", "2023-11-06", "2023-10-09"]).stdout,
+        "4 teaching weeks apart, in Autumn term\n"
+    );
+    assert_eq!(uoyweek(&["diff", "2023-10-09", "bogus"]).status, 4);
+}
+
+#[test]
+fn since_counts_teaching_weeks_to_now() {
+    assert_eq!(
+        uoyweek(&["--date", "2024-01-17", "--since", "2023-10-09"]).stdout,
+        "9 teaching weeks since 2023-10-09\n"
+    );
+    // Counting forwards to a later date, rather than back.
+    assert_eq!(
+        uoyweek(&["--date", "2023-10-09", "--since", "2024-01-17"]).stdout,
+        "9 teaching weeks until 2024-01-17\n"
+    );
+}
+
+#[cfg(feature = "notify")]
+#[test]
+fn notify_does_nothing_away_from_a_boundary() {
+    let run = uoyweek(&["--date", "2023-10-09", "--notify"]);
+    assert_eq!((run.stdout.as_str(), run.status), ("", 0));
+}
+
+#[test]
+fn stdin_gets_a_line_per_date() {
+    let run = uoyweek_with(&["--stdin"], &[], "2023-10-09\nnonsense\n25/12/2023\n");
+    assert_eq!(
+        run.stdout,
+        "Aut/3/Mon\n\
+         error: invalid date \"nonsense\" (tried YYYY-MM-DD, DD/MM/YYYY and RFC 3339)\n\
+         n/a\n"
+    );
+    assert_eq!(run.status, 0);
+    let run = uoyweek_with(&["--stdin"], &[], "2024-01-08\n2023-12-02\n2023-10-09\n");
+    assert_eq!(run.stdout, "Spr/1/Mon\n(Aut/10/Sat)\nAut/3/Mon\n");
+}
+
+#[test]
+fn repl_moves_by_weeks_from_the_last_date() {
+    let run = uoyweek_with(
+        &["repl"],
+        &[("UOYWEEK_NOW", "2023-10-09T10:00:00+01:00")],
+        "today\n+2\n-1\n\n2024-01-08\n+1\nbogus\n+x\ntoday\n",
+    );
+    assert_eq!(
+        run.stdout,
+        "2023-10-09: Aut/3/Mon\n\
+         2023-10-23: Aut/5/Mon\n\
+         2023-10-16: Aut/4/Mon\n\
+         2024-01-08: Spr/1/Mon\n\
+         2024-01-15: Spr/2/Mon\n\
+         error: invalid date \"bogus\" (tried YYYY-MM-DD, DD/MM/YYYY and RFC 3339)\n\
+         error: invalid number of weeks \"+x\"\n\
+         2023-10-09: Aut/3/Mon\n"
+    );
+    // Not a terminal, so no prompts.
+    assert_eq!(run.stderr, "");
+    assert_eq!(run.status, 0);
+}
+
+#[test]
+fn now_can_be_set_from_the_environment() {
+    let now = |value| uoyweek_with(&[], &[("UOYWEEK_NOW", value)], "");
+    assert_eq!(now("2023-10-09T10:00:00+01:00").stdout, "Aut/3/Mon\n");
+    // Late on Sunday in UTC is already Monday in York.
+    assert_eq!(now("2023-10-08T23:30:00Z").stdout, "Aut/3/Mon\n");
+    let run = now("bogus");
+    assert_eq!(run.status, 4);
+    assert!(
+        run.stderr
+            .starts_with("uoyweek: invalid UOYWEEK_NOW \"bogus\""),
+        "{}",
+        run.stderr
+    );
+    // `--date` still wins.
+    let run = uoyweek_with(
+        &["--date", "2024-01-08"],
+        &[("UOYWEEK_NOW", "2023-10-09T10:00:00+01:00")],
+        "",
+    );
+    assert_eq!(run.stdout, "Spr/1/Mon\n");
+}
+
+#[test]
+fn terms_file_can_be_set_from_the_environment() {
+    let one = "[[term]]\nname = \"Autumn\"\nstart = \"2023-10-02\"\nend = \"2023-12-08\"\n";
+    let one = TermsFile::new("env-one", one);
+    let run = uoyweek_with(
+        &["--date", "2023-10-09"],
+        &[("UOYWEEK_TERMS", one.path())],
+        "",
+    );
+    assert_eq!(
+        (run.stdout.as_str(), run.stderr.as_str()),
+        ("Aut/2/Mon\n", "")
+    );
+    // `--terms-file` still wins.
+    let two = "[[term]]\nname = \"Autumn\"\nstart = \"2023-10-09\"\nend = \"2023-12-15\"\n";
+    let two = TermsFile::new("env-two", two);
+    let run = uoyweek_with(
+        &["--date", "2023-10-09", "--terms-file", two.path()],
+        &[("UOYWEEK_TERMS", one.path())],
+        "",
+    );
+    assert_eq!(run.stdout, "Aut/1/Mon\n");
+    // A broken file is only a warning, and the built-in dates are used instead.
+    let bad = TermsFile::new("env-bad", "garbage");
+    let run = uoyweek_with(
+        &["--date", "2023-10-09"],
+        &[("UOYWEEK_TERMS", bad.path())],
+        "",
+    );
+    assert_eq!((run.stdout.as_str(), run.status), ("Aut/3/Mon\n", 0));
+    assert!(
+        run.stderr
+            .starts_with("uoyweek: warning: using built-in term dates"),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn next_event_counts_down() {
+    let run = uoyweek(&["next-event", "--date", "2023-12-25"]);
+    assert_eq!(run.stdout, "Spring term starts in 14 days (2024-01-08)\n");
+    assert_eq!(run.status, 0);
+    let run = uoyweek(&["next-event", "--date", "2031-01-01"]);
+    assert_eq!(run.stdout, "No more terms known\n");
+    assert_eq!(run.status, 3);
+}
+
+#[test]
+fn prev_and_next_term_bracket_the_vacation() {
+    let term = |command, date| uoyweek(&["--date", date, command]).stdout;
+    assert_eq!(
+        term("prev-term", "2023-12-25"),
+        "Autumn 2023 (Mon 25 Sep – Fri 01 Dec)\n"
+    );
+    assert_eq!(
+        term("next-term", "2023-12-25"),
+        "Spring 2024 (Mon 08 Jan – Fri 15 Mar)\n"
+    );
+    // In term, they skip the current one.
+    assert_eq!(
+        term("prev-term", "2023-10-09"),
+        "Summer 2023 (Mon 17 Apr – Fri 23 Jun)\n"
+    );
+    let run = uoyweek(&["--date", "2015-09-01", "prev-term"]);
+    assert_eq!(
+        (run.stdout.as_str(), run.status),
+        ("No earlier terms known\n", 3)
+    );
+    let run = uoyweek(&["--date", "2028-08-01", "next-term"]);
+    assert_eq!(
+        (run.stdout.as_str(), run.status),
+        ("No more terms known\n", 3)
+    );
+}
+
+#[cfg(feature = "tz")]
+#[test]
+fn timezone_changes_the_day() {
+    // 01:30 on Monday in Auckland, but still Sunday in York.
+    let time = "2023-10-08T12:30:00Z";
+    assert_eq!(uoyweek(&["--date", time]).stdout, "Aut/2/Sun\n");
+    assert_eq!(
+        uoyweek(&["--date", time, "--timezone", "Pacific/Auckland"]).stdout,
+        "Aut/3/Mon\n"
+    );
+    // 00:30 on Monday in York, but still Sunday evening in New York.
+    let time = "2023-10-09T00:30:00+01:00";
+    assert_eq!(uoyweek(&["--date", time]).stdout, "Aut/3/Mon\n");
+    assert_eq!(
+        uoyweek(&["--date", time, "--timezone", "America/New_York"]).stdout,
+        "Aut/2/Sun\n"
+    );
+    let run = uoyweek(&["--timezone", "Nowhere/Special"]);
+    assert_eq!(run.status, 4);
+    assert!(
+        run.stderr.contains("expected a name like Europe/London"),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn completions_are_generated() {
+    let run = uoyweek(&["completions", "bash"]);
+    assert_eq!(run.status, 0);
+    assert!(run.stdout.contains("_uoyweek()"), "{}", run.stdout);
+    assert!(run.stdout.contains("--weeknum-only"));
+    assert!(uoyweek(&["completions", "fish"])
+        .stdout
+        .contains("complete -c uoyweek"));
+}
+
+#[test]
+fn continuous_counts_from_autumn() {
+    assert_eq!(
+        uoyweek(&["--date", "2024-01-08", "--continuous"]).stdout,
+        "16\n"
+    );
+    assert_eq!(
+        uoyweek(&["--date", "2023-12-25", "--continuous"]).stdout,
+        "14\n"
+    );
+}
+
+#[test]
+fn progress_through_term() {
+    let progress = |args: &[&str]| uoyweek(&[&["--progress"], args].concat()).stdout;
+    assert_eq!(
+        progress(&["--date", "2023-09-25"]),
+        "Aut/1/Mon (week 1 of 10, 10% through term)\n"
+    );
+    assert_eq!(
+        progress(&["--date", "2023-12-01"]),
+        "Aut/10/Fri (week 10 of 10, 100% through term)\n"
+    );
+    // The arrivals week doesn't make term any longer.
+    assert_eq!(
+        progress(&["--date", "2023-09-18", "--week-zero"]),
+        "(Aut/0/Mon) (week 0 of 10, 9% through term)\n"
+    );
+    assert_eq!(progress(&["--date", "2023-12-25"]), "n/a (not in term)\n");
+}
+
+#[test]
+fn color_follows_the_status() {
+    let color = |date, when| uoyweek(&["--date", date, "--color", when]).stdout;
+    assert_eq!(color("2023-10-09", "always"), "\x1b[32mAut/3/Mon\x1b[0m\n");
+    assert_eq!(
+        color("2023-12-02", "always"),
+        "\x1b[33m(Aut/10/Sat)\x1b[0m\n"
+    );
+    assert_eq!(color("2023-12-25", "always"), "\x1b[2mn/a\x1b[0m\n");
+    assert_eq!(color("2023-10-09", "never"), "Aut/3/Mon\n");
+    // The tests' output isn't a terminal.
+    assert_eq!(color("2023-10-09", "auto"), "Aut/3/Mon\n");
+}
+
+#[test]
+fn no_parens_drops_the_parentheses() {
+    assert_eq!(uoyweek(&["--date", "2023-12-02"]).stdout, "(Aut/10/Sat)\n");
+    let run = uoyweek(&["--date", "2023-12-02", "--no-parens"]);
+    assert_eq!(run.stdout, "Aut/10/Sat\n");
+    // It's still only partly in term.
+    assert_eq!(run.status, 1);
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn debug_logs_say_how_the_term_was_chosen() {
+    let run = uoyweek_with(&["--date", "2023-12-02"], &[("RUST_LOG", "debug")], "");
+    assert_eq!(run.stdout, "(Aut/10/Sat)\n");
+    assert!(run.stderr.contains("get_strict_term"), "{}", run.stderr);
+    assert!(run.stderr.contains("get_term"), "{}", run.stderr);
+    assert!(run.stderr.contains("Autumn 2023 (Mon 25 Sep – Fri 01 Dec)"));
+    let run = uoyweek_with(&["--date", "2023-12-02"], &[("RUST_LOG", "warn")], "");
+    assert_eq!(run.stderr, "");
+}
+
+#[test]
+fn na_text_replaces_na() {
+    assert_eq!(
+        uoyweek(&["--date", "2023-12-25", "--na-text", "vacation"]).stdout,
+        "vacation\n"
+    );
+    assert_eq!(
+        uoyweek(&["--date", "2023-12-25", "--na-text", "", "--weeknum-only"]).stdout,
+        "\n"
+    );
+    // It's only for out of term.
+    assert_eq!(
+        uoyweek(&["--date", "2023-10-09", "--na-text", "vacation"]).stdout,
+        "Aut/3/Mon\n"
+    );
+}
+
+#[test]
+fn remaining_counts_days() {
+    let remaining = |date| uoyweek(&["--date", date, "--remaining"]).stdout;
+    assert_eq!(remaining("2023-11-28"), "4 days left in Autumn term\n");
+    assert_eq!(remaining("2023-12-01"), "1 day left in Autumn term\n");
+    assert_eq!(
+        remaining("2023-10-11"),
+        "7 weeks 3 days left in Autumn term\n"
+    );
+    assert_eq!(remaining("2023-12-25"), "2 weeks until Spring term\n");
+    assert_eq!(remaining("2031-12-25"), "n/a\n");
+}
+
+#[test]
+fn utc_goes_by_the_date_in_utc() {
+    // 00:30 on Monday in York is still Sunday in UTC, during British Summer Time.
+    let time = "2023-10-08T23:30:00Z";
+    assert_eq!(uoyweek(&["--date", time]).stdout, "Aut/3/Mon\n");
+    assert_eq!(uoyweek(&["--date", time, "--utc"]).stdout, "Aut/2/Sun\n");
+    // In winter, they're the same.
+    let time = "2023-11-05T23:30:00Z";
+    assert_eq!(uoyweek(&["--date", time]).stdout, "Aut/6/Sun\n");
+    assert_eq!(uoyweek(&["--date", time, "--utc"]).stdout, "Aut/6/Sun\n");
+}
+
+#[test]
+fn boundary_hour_moves_the_start_of_the_week() {
+    let early = |args: &[&str]| {
+        let mut all = vec!["--date", "2023-10-09T05:00:00+01:00"];
+        all.extend_from_slice(args);
+        uoyweek(&all).stdout
+    };
+    assert_eq!(early(&[]), "Aut/3/Mon\n");
+    assert_eq!(early(&["--boundary-hour", "6"]), "Aut/2/Sun\n");
+    assert_eq!(early(&["--boundary-hour", "5"]), "Aut/3/Mon\n");
+    assert_eq!(uoyweek(&["--boundary-hour", "24"]).status, 4);
+}
+
+#[test]
+fn pad_zero_pads_week_numbers() {
+    assert_eq!(
+        uoyweek(&["--date", "2023-10-09", "--pad", "2"]).stdout,
+        "Aut/03/Mon\n"
+    );
+    assert_eq!(
+        uoyweek(&["--date", "2023-10-09", "--pad", "2", "--weeknum-only"]).stdout,
+        "03\n"
+    );
+    assert_eq!(uoyweek(&["--pad", "-1"]).status, 4);
+}
+
+#[test]
+fn calendar_with_the_weekend_first() {
+    let run = uoyweek(&[
+        "--weekend-next",
+        "calendar",
+        "--term",
+        "aut",
+        "--year",
+        "2023",
+    ]);
+    let rows: Vec<_> = run.stdout.lines().collect();
+    assert_eq!(rows[1], "Week  Sat  Sun  Mon  Tue  Wed  Thu  Fri");
+    // 29 October, when the clocks go back, only once.
+    assert_eq!(
+        rows[7],
+        "   6  28   29   30   31    1    2    3   November 2023"
+    );
+    assert_eq!(rows.len(), 12);
+}
+
+#[test]
+fn prompt_is_short_and_unterminated() {
+    assert_eq!(
+        uoyweek(&["--date", "2023-10-09", "--prompt"]).stdout,
+        "Aut3"
+    );
+    assert_eq!(
+        uoyweek(&["--date", "2023-12-02", "--prompt"]).stdout,
+        "Aut10"
+    );
+    assert_eq!(uoyweek(&["--date", "2023-12-25", "--prompt"]).stdout, "");
+}
+
+#[test]
+fn tmux_is_unterminated() {
+    assert_eq!(uoyweek(&["--date", "2023-10-09", "--tmux"]).stdout, "Aut/3");
+    assert_eq!(uoyweek(&["--date", "2023-12-25", "--tmux"]).stdout, "");
+}
+
+#[test]
+fn list_as_markdown() {
+    let out = uoyweek(&["list", "--markdown"]).stdout;
+    let lines: Vec<_> = out.lines().collect();
+    assert_eq!(lines[0], "| Academic Year | Term | Start | End | Weeks |");
+    assert_eq!(lines[1], "| --- | --- | --- | --- | --- |");
+    assert!(lines.contains(&"| 2023/24 | Autumn | 2023-09-25 | 2023-12-01 | 10 |"));
+    assert_eq!(lines.len() - 2, builtin_rows().len());
+}
+
+#[test]
+fn list_totals_each_year() {
+    let run = uoyweek(&["list"]);
+    assert!(
+        run.stdout.contains(
+            "2023/24 (30 weeks):\n    \
+             Autumn 2023 (Mon 25 Sep – Fri 01 Dec)\n    \
+             Spring 2024 (Mon 08 Jan – Fri 15 Mar)\n    \
+             Summer 2024 (Mon 15 Apr – Fri 21 Jun)\n"
+        ),
+        "{}",
+        run.stdout
+    );
+    let short = "[[term]]\nname = \"Autumn\"\nstart = \"2023-09-25\"\nend = \"2023-10-06\"\n";
+    let file = TermsFile::new("list", short);
+    assert_eq!(
+        uoyweek(&["--terms-file", file.path(), "list"]).stdout,
+        "2023/24 (2 weeks):\n    Autumn 2023 (Mon 25 Sep – Fri 06 Oct)\n"
+    );
+}
+
+#[test]
+fn list_as_csv_round_trips() {
+    let csv = uoyweek(&["list", "--csv"]).stdout;
+    let mut rows = csv.lines();
+    assert_eq!(rows.next(), Some("academic_year,term,start,end,weeks"));
+    let rows: Vec<Vec<&str>> = rows.map(|row| row.split(',').collect()).collect();
+    // The same terms as `terms.csv`.
+    let terms: Vec<String> = rows.iter().map(|row| row[1..4].join(",")).collect();
+    assert_eq!(terms, builtin_rows());
+    // Reading them back in gives the same table.
+    let toml: String = rows
+        .iter()
+        .map(|row| {
+            format!(
+                "[[term]]\nname = {:?}\nstart = {:?}\nend = {:?}\n\n",
+                row[1], row[2], row[3]
+            )
+        })
+        .collect();
+    let file = TermsFile::new("csv", &toml);
+    let run = uoyweek(&["--terms-file", file.path(), "list", "--csv"]);
+    assert_eq!(run.stderr, "");
+    assert_eq!(run.stdout, csv);
+}
+
+#[test]
+fn verify_lists_suspicious_terms() {
+    let run = uoyweek(&["--verify"]);
+    assert_eq!(
+        run.stdout,
+        format!("All {} terms look normal\n", builtin_rows().len())
+    );
+    assert_eq!(run.status, 0);
+    let file = TermsFile::new(
+        "verify",
+        "[[term]]\nname = \"Autumn\"\nstart = \"2023-09-26\"\nend = \"2024-01-01\"\n\n\
+         [[term]]\nname = \"Spring\"\nstart = \"2024-01-08\"\nend = \"2024-03-15\"\n",
+    );
+    let run = uoyweek(&["--terms-file", file.path(), "--verify"]);
+    assert_eq!(
+        run.stdout,
+        "Autumn 2023 (Tue 26 Sep – Mon 01 Jan): 15 weeks long (expected 9 to 11 weeks), \
+         starts on a Tuesday\n"
+    );
+    assert_eq!(run.status, 4);
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn update_refuses_to_overwrite() {
+    let file = TermsFile::new("update", "# my terms\n");
+    let run = uoyweek(&["update", "--out", file.path()]);
+    assert_eq!(run.status, 4);
+    assert!(run.stderr.contains("use --force"), "{}", run.stderr);
+    assert_eq!(fs::read_to_string(file.path()).unwrap(), "# my terms\n");
+}
+
+/// A `uoyweek --serve` running in the background, which is killed when dropped.
+#[cfg(feature = "serve")]
+struct Server {
+    child: std::process::Child,
+    addr: String,
+}
+
+#[cfg(feature = "serve")]
+impl Server {
+    /// Starts serving on a free port, as if it were `now`.
+    fn start(now: &str) -> Server {
+        use std::io::{BufRead, BufReader};
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_uoyweek"))
+            .args(["--serve", "127.0.0.1:0"])
+            .env("UOYWEEK_NOW", now)
+            .env_remove("UOYWEEK_TERMS")
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("uoyweek runs");
+        let mut line = String::new();
+        BufReader::new(child.stderr.take().expect("stderr is piped"))
+            .read_line(&mut line)
+            .expect("uoyweek says where it's listening");
+        let addr = line
+            .trim()
+            .strip_prefix("uoyweek: listening on http://")
+            .unwrap_or_else(|| panic!("unexpected output {:?}", line))
+            .to_string();
+        Server { child, addr }
+    }
+
+    /// Fetches `path`, returning the response's headers and body.
+    fn get(&self, path: &str) -> (String, String) {
+        use std::{io::Read, net::TcpStream};
+
+        let mut stream = TcpStream::connect(&self.addr).expect("server accepts connections");
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            path, self.addr
+        )
+        .expect("server reads requests");
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .expect("server responds");
+        let (headers, mut body) = response
+            .split_once("\r\n\r\n")
+            .expect("response has headers");
+        if !headers.contains("Transfer-Encoding: chunked") {
+            return (headers.to_string(), body.to_string());
+        }
+        // Longer responses come in chunks, each after its length in hex.
+        let mut unchunked = String::new();
+        loop {
+            let (size, rest) = body.split_once("\r\n").expect("chunk has a size");
+            let size = usize::from_str_radix(size, 16).expect("chunk size is hex");
+            if size == 0 {
+                return (headers.to_string(), unchunked);
+            }
+            unchunked.push_str(&rest[..size]);
+            body = &rest[size + 2..];
+        }
+    }
+}
+
+#[cfg(feature = "serve")]
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(feature = "serve")]
+#[test]
+fn serve_answers_with_the_week() {
+    let server = Server::start("2023-10-09T12:00:00+01:00");
+    let (headers, body) = server.get("/json");
+    assert!(headers.starts_with("HTTP/1.1 200"), "{}", headers);
+    assert!(headers.contains("application/json"), "{}", headers);
+    assert_eq!(
+        body,
+        r#"{"term":"Autumn","term_short":"Aut","week":3,"weekday":"Mon","in_strict_term":true,"partial_week":false}"#
+    );
+    assert_eq!(server.get("/").1, "Aut/3/Mon\n");
+    assert!(server.get("/nowhere").0.starts_with("HTTP/1.1 404"));
+}
+
+#[cfg(feature = "serve")]
+#[test]
+fn serve_ignores_query_strings() {
+    let server = Server::start("2023-10-09T12:00:00+01:00");
+    assert_eq!(server.get("/?x=1").1, "Aut/3/Mon\n");
+    assert!(server.get("/json?x=1").0.starts_with("HTTP/1.1 200"));
+}
+
+#[cfg(feature = "serve")]
+#[test]
+fn serve_says_na_out_of_term() {
+    let server = Server::start("2024-08-01T12:00:00+01:00");
+    let (headers, body) = server.get("/");
+    assert!(headers.starts_with("HTTP/1.1 200"), "{}", headers);
+    assert_eq!(body, "n/a\n");
+}
+
+#[cfg(feature = "serve")]
+#[test]
+fn serve_has_a_calendar_feed() {
+    let server = Server::start("2023-10-09T12:00:00+01:00");
+    let (headers, body) = server.get("/terms.ics");
+    assert!(headers.starts_with("HTTP/1.1 200"), "{}", headers);
+    assert!(headers.contains("text/calendar"), "{}", headers);
+    assert!(body.starts_with("BEGIN:VCALENDAR\r\n"));
+    // The same events as `--ical`.
+    let events = |ics: &str| ics.matches("BEGIN:VEVENT").count();
+    assert_eq!(events(&body), events(&uoyweek(&["--ical"]).stdout));
+    assert!(body.contains("SUMMARY:Aut/3\r\n"));
+}
+
+#[cfg(feature = "serve")]
+#[test]
+fn serve_has_metrics() {
+    let server = Server::start("2023-10-09T12:00:00+01:00");
+    let (headers, body) = server.get("/metrics");
+    assert!(headers.starts_with("HTTP/1.1 200"), "{}", headers);
+    assert!(
+        body.contains("uoyweek_in_term{strict=\"true\"} 1\n"),
+        "{}",
+        body
+    );
+    let week = body
+        .lines()
+        .find_map(|line| line.strip_prefix("uoyweek_week_number "))
+        .expect("there's a week number in term");
+    assert_eq!(week.parse::<i64>(), Ok(3));
+    assert!(
+        body.contains("uoyweek_days_until_next_term 91\n"),
+        "{}",
+        body
+    );
+}
@@ -0,0 +1,41 @@
+//! Property tests for week numbers, over every day of the built-in terms.
+
+use chrono::{prelude::*, Duration};
+use proptest::prelude::*;
+use uoyweek::{builtin_terms, midnight, zone::Tz, Term};
+
+/// A built-in term, and a day in it (counting the days from `loose_start` to `loose_end`).
+fn term_and_day() -> impl Strategy<Value = (Term, NaiveDate)> {
+    let terms = builtin_terms();
+    (0..terms.len()).prop_flat_map(move |i| {
+        let term = terms[i];
+        let first = term.loose_start().date_naive();
+        let days = (term.loose_end().date_naive() - first).num_days();
+        (Just(term), 0..days).prop_map(move |(term, n)| (term, first + Duration::days(n)))
+    })
+}
+
+/// Midday on `date` in York.
+fn at(date: NaiveDate) -> DateTime<Tz> {
+    midnight(date).unwrap() + Duration::hours(12)
+}
+
+proptest! {
+    #[test]
+    fn week_numbers_never_go_down((term, day) in term_and_day()) {
+        let next = day + Duration::days(1);
+        prop_assert!(term.week_number(at(next)) >= term.week_number(at(day)));
+    }
+
+    #[test]
+    fn week_numbers_go_up_by_one_a_week((term, day) in term_and_day()) {
+        let next = day + Duration::weeks(1);
+        prop_assert_eq!(term.week_number(at(next)), term.week_number(at(day)) + 1);
+    }
+
+    #[test]
+    fn loose_start_is_in_week_1(i in 0..builtin_terms().len()) {
+        let term = &builtin_terms()[i];
+        prop_assert_eq!(term.week_number(term.loose_start()), 1);
+    }
+}